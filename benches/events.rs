@@ -0,0 +1,56 @@
+//! Benchmarks the decoding of websocket events.
+//!
+//! Run via `cargo bench --bench events`.
+
+use comfyui_client::meta::{ComfyEvent, DecodeOptions};
+use serde_json::Value;
+use std::{hint::black_box, time::Instant};
+
+const ITERATIONS: u32 = 100_000;
+
+const PROGRESS: &str = r#"{"type": "progress", "data": {"value": 12, "max": 20, "prompt_id": "0b6a3e4c-5c2f-4a8e-9f0e-1d2c3b4a5f6e", "node": "3"}}"#;
+
+const EXECUTED: &str = r#"{"type": "executed", "data": {"node": "9", "display_node": "9", "output": {"images": [{"filename": "ComfyUI_00001_.png", "subfolder": "", "type": "output"}]}, "prompt_id": "0b6a3e4c-5c2f-4a8e-9f0e-1d2c3b4a5f6e"}}"#;
+
+const MONITOR: &str = r#"{"type": "crystools.monitor", "data": {"cpu_utilization": 12.5, "ram_total": 68719476736, "ram_used": 21474836480, "ram_used_percent": 31.2, "hdd_total": 1000204886016, "hdd_used": 500102443008, "hdd_used_percent": 50.0, "device_type": "cuda", "gpus": [{"gpu_utilization": 98, "gpu_temperature": 71, "vram_total": 25769803776, "vram_used": 20615843020, "vram_used_percent": 80.0}]}}"#;
+
+/// Decodes an event like the client did before the direct decoding, via an
+/// intermediate `Value` which is cloned.
+fn decode_via_value(json: &str) -> ComfyEvent {
+    let value = serde_json::from_str::<Value>(json).unwrap();
+    serde_json::from_value(value.clone()).unwrap_or(ComfyEvent::Unknown(value))
+}
+
+fn bench(name: &str, mut f: impl FnMut()) {
+    for _ in 0..ITERATIONS / 10 {
+        f();
+    }
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        f();
+    }
+    let nanos = start.elapsed().as_nanos() / u128::from(ITERATIONS);
+    println!("{name:<40} {nanos:>8} ns");
+}
+
+fn main() {
+    for (event, json) in [
+        ("progress", PROGRESS),
+        ("executed", EXECUTED),
+        ("crystools.monitor", MONITOR),
+    ] {
+        bench(&format!("{event} via value"), || {
+            black_box(decode_via_value(black_box(json)));
+        });
+        bench(&format!("{event} direct"), || {
+            black_box(ComfyEvent::from_json(black_box(json), DecodeOptions::default()).unwrap());
+        });
+        bench(&format!("{event} direct, raw unknown"), || {
+            let options = DecodeOptions {
+                raw_unknown: true,
+                ..Default::default()
+            };
+            black_box(ComfyEvent::from_json(black_box(json), options).unwrap());
+        });
+    }
+}
@@ -163,6 +163,9 @@ async fn main() {
                 ComfyEvent::Progress { data } => {
                     debug!(data:?; "receive process event");
                 }
+                ComfyEvent::ProgressState { data } => {
+                    debug!(data:?; "receive progress state event");
+                }
                 ComfyEvent::Executing { data } => {
                     debug!(data:?; "receive executing event");
                 }
@@ -192,12 +195,20 @@ async fn main() {
                     error!(data:?; "receive execution_interrupted_event");
                     break;
                 }
+                ComfyEvent::Logs { data } => {
+                    debug!(data:?; "receive logs event");
+                }
                 ComfyEvent::Unknown(event) => {
                     // exclude monitoring events of `ComfyUI-Crystools` plugin
                     if event["type"] != json!("crystools.monitor") {
                         warn!(event:?; "receive unknown comfy event");
                     }
                 }
+                ComfyEvent::Raw(event) => {
+                    if event.event_type() != "crystools.monitor" {
+                        warn!(event:% = event.json(); "receive unknown comfy event");
+                    }
+                }
             },
             Event::Connection(event) => {
                 warn!(event:?; "receive connection event");
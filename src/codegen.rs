@@ -0,0 +1,283 @@
+use crate::{
+    ClientError, ClientResult,
+    meta::{InputSpec, InputType, NodeInfo, ObjectInfo},
+};
+use std::{collections::HashSet, fmt::Write};
+
+/// The keywords of Rust which can't be used as identifiers as is.
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "box", "break", "const", "continue", "crate", "dyn", "else", "enum",
+    "extern", "false", "fn", "for", "gen", "if", "impl", "in", "let", "loop", "match", "mod",
+    "move", "mut", "pub", "ref", "return", "self", "Self", "static", "struct", "super", "trait",
+    "true", "type", "unsafe", "use", "where", "while", "yield",
+];
+
+/// Generates typed node structs from the node schemas of a server.
+///
+/// Each generated struct has a field per required and optional input of the
+/// node, and implements [`TypedNode`](crate::workflow::TypedNode), so it can
+/// be added to a [`Workflow`](crate::workflow::Workflow) via
+/// [`Workflow::add`](crate::workflow::Workflow::add). Inputs of primitive
+/// types map to Rust primitives, combo inputs to [`String`], and all other
+/// inputs, e.g. `MODEL`, to a [`Link`](crate::workflow::Link).
+///
+/// The generated code is meant to be written from a build script, or saved
+/// once from a captured `object_info` response, and then included via
+/// `include!`. It requires `serde_json` as dependency of the including
+/// crate.
+///
+/// # Example
+///
+/// ```no_run
+/// // build.rs
+/// use comfyui_client::{codegen::generate_nodes, meta::ObjectInfo};
+///
+/// let json = std::fs::read_to_string("object_info.json").unwrap();
+/// let object_info = serde_json::from_str::<ObjectInfo>(&json).unwrap();
+/// let code = generate_nodes(&object_info, &["KSampler", "CLIPTextEncode"]).unwrap();
+/// let out_dir = std::env::var("OUT_DIR").unwrap();
+/// std::fs::write(format!("{out_dir}/nodes.rs"), code).unwrap();
+/// ```
+///
+/// # Parameters
+///
+/// - `object_info`: The node schemas, e.g. as returned by
+///   [`ComfyUIClient::get_object_info`](crate::ComfyUIClient::get_object_info).
+/// - `class_types`: The classes of the nodes to generate structs for.
+///
+/// # Returns
+///
+/// The generated Rust code, or an error if a class is not found.
+pub fn generate_nodes(object_info: &ObjectInfo, class_types: &[&str]) -> ClientResult<String> {
+    let mut code = String::from("// @generated by comfyui_client::codegen::generate_nodes\n");
+    let mut struct_names = HashSet::new();
+    for class_type in class_types {
+        let info = object_info
+            .get(*class_type)
+            .ok_or_else(|| ClientError::NodeNotFound(class_type.to_string()))?;
+        let struct_name = unique_ident(struct_name(class_type), &mut struct_names);
+        generate_node(&mut code, class_type, &struct_name, info);
+    }
+    Ok(code)
+}
+
+/// An input of a generated node struct.
+struct Field<'a> {
+    name: &'a str,
+    ident: String,
+    rust_type: &'static str,
+    optional: bool,
+}
+
+fn generate_node(code: &mut String, class_type: &str, struct_name: &str, info: &NodeInfo) {
+    let mut idents = HashSet::new();
+    let fields = ["required", "optional"]
+        .into_iter()
+        .flat_map(|group| {
+            let specs = match group {
+                "required" => &info.input.required,
+                _ => &info.input.optional,
+            };
+            let mut names = match info.input_order.get(group) {
+                Some(order) => order.iter().map(String::as_str).collect::<Vec<_>>(),
+                None => specs.keys().map(String::as_str).collect::<Vec<_>>(),
+            };
+            if !info.input_order.contains_key(group) {
+                names.sort_unstable();
+            }
+            names
+                .into_iter()
+                .filter_map(move |name| Some((name, specs.get(name)?, group == "optional")))
+        })
+        .map(|(name, spec, optional)| Field {
+            name,
+            ident: unique_ident(field_ident(name), &mut idents),
+            rust_type: rust_type(spec),
+            optional,
+        })
+        .collect::<Vec<_>>();
+
+    let display_name = match info.display_name.as_str() {
+        "" => class_type,
+        display_name => display_name,
+    };
+    let _ = writeln!(code);
+    let _ = writeln!(code, "/// {}", doc_line(display_name));
+    if !info.description.is_empty() {
+        let _ = writeln!(code, "///");
+        let _ = writeln!(code, "/// {}", doc_line(&info.description));
+    }
+    if !info.output.is_empty() {
+        let _ = writeln!(code, "///");
+        let _ = writeln!(code, "/// Outputs:");
+        for (index, output_type) in info.output.iter().enumerate() {
+            let name = info.output_name.get(index).unwrap_or(output_type);
+            let _ = writeln!(
+                code,
+                "/// - {index}: `{}` ({})",
+                doc_line(name),
+                doc_line(output_type)
+            );
+        }
+    }
+    let _ = writeln!(code, "#[derive(Clone, Debug)]");
+    let _ = writeln!(code, "pub struct {struct_name} {{");
+    for field in &fields {
+        let _ = writeln!(code, "    /// The `{}` input.", doc_line(field.name));
+        if field.optional {
+            let _ = writeln!(
+                code,
+                "    pub {}: Option<{}>,",
+                field.ident, field.rust_type
+            );
+        } else {
+            let _ = writeln!(code, "    pub {}: {},", field.ident, field.rust_type);
+        }
+    }
+    let _ = writeln!(code, "}}");
+    let _ = writeln!(code);
+    let _ = writeln!(
+        code,
+        "impl ::comfyui_client::workflow::TypedNode for {struct_name} {{"
+    );
+    let _ = writeln!(code, "    const CLASS_TYPE: &'static str = {class_type:?};");
+    let _ = writeln!(code);
+    let _ = writeln!(code, "    fn inputs(&self) -> ::serde_json::Value {{");
+    let _ = writeln!(
+        code,
+        "        let {}inputs = ::serde_json::Map::new();",
+        if fields.is_empty() { "" } else { "mut " }
+    );
+    for field in &fields {
+        let clone = match field.rust_type {
+            "String" | "::comfyui_client::workflow::Link" => ".clone()",
+            _ => "",
+        };
+        if field.optional {
+            let _ = writeln!(
+                code,
+                "        if let Some(value) = &self.{} {{",
+                field.ident
+            );
+            let _ = writeln!(
+                code,
+                "            inputs.insert({:?}.to_string(), ::serde_json::Value::from({}));",
+                field.name,
+                if clone.is_empty() {
+                    "*value"
+                } else {
+                    "value.clone()"
+                }
+            );
+            let _ = writeln!(code, "        }}");
+        } else {
+            let _ = writeln!(
+                code,
+                "        inputs.insert({:?}.to_string(), \
+                 ::serde_json::Value::from(self.{}{clone}));",
+                field.name, field.ident
+            );
+        }
+    }
+    let _ = writeln!(code, "        ::serde_json::Value::Object(inputs)");
+    let _ = writeln!(code, "    }}");
+    let _ = writeln!(code, "}}");
+}
+
+/// Returns the Rust type of the field of an input.
+fn rust_type(spec: &InputSpec) -> &'static str {
+    match &spec.r#type {
+        InputType::Combo(_) => "String",
+        InputType::Named(name) => match name.as_str() {
+            "INT" if spec.options.min.is_some_and(|min| min >= 0.0) => "u64",
+            "INT" => "i64",
+            "FLOAT" => "f64",
+            "STRING" | "COMBO" => "String",
+            "BOOLEAN" => "bool",
+            _ => "::comfyui_client::workflow::Link",
+        },
+    }
+}
+
+/// Returns the name of the struct of a node class, e.g. `KSampler` or
+/// `ImageBlur` for `Image Blur`.
+fn struct_name(class_type: &str) -> String {
+    let mut name = String::new();
+    let mut upper = true;
+    for c in class_type.chars() {
+        if c.is_ascii_alphanumeric() {
+            if upper {
+                name.push(c.to_ascii_uppercase());
+            } else {
+                name.push(c);
+            }
+            upper = false;
+        } else {
+            upper = true;
+        }
+    }
+    if !name.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        name.insert_str(0, "Node");
+    }
+    name
+}
+
+/// Returns the name of the field of an input, e.g. `ckpt_name`.
+fn field_ident(name: &str) -> String {
+    let mut ident = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>();
+    if !ident.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+        ident.insert(0, '_');
+    }
+    match ident.as_str() {
+        "_" => "_input".to_string(),
+        "self" | "Self" | "super" | "crate" => format!("{ident}_"),
+        _ if KEYWORDS.contains(&ident.as_str()) => format!("r#{ident}"),
+        _ => ident,
+    }
+}
+
+/// Makes an identifier unique by appending a number.
+fn unique_ident(ident: String, taken: &mut HashSet<String>) -> String {
+    let mut unique = ident.clone();
+    let mut suffix = 1;
+    while !taken.insert(unique.clone()) {
+        suffix += 1;
+        unique = format!("{ident}{suffix}");
+    }
+    unique
+}
+
+/// Strips line breaks from text written into a doc comment.
+fn doc_line(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_idents() {
+        assert_eq!(struct_name("KSampler"), "KSampler");
+        assert_eq!(struct_name("Image Blur"), "ImageBlur");
+        assert_eq!(struct_name("3d-loader"), "Node3dLoader");
+        assert_eq!(field_ident("ckpt_name"), "ckpt_name");
+        assert_eq!(field_ident("type"), "r#type");
+        assert_eq!(field_ident("self"), "self_");
+        assert_eq!(field_ident("Image.2"), "image_2");
+        assert_eq!(field_ident("2x"), "_2x");
+
+        let mut taken = HashSet::new();
+        assert_eq!(unique_ident("image".to_string(), &mut taken), "image");
+        assert_eq!(unique_ident("image".to_string(), &mut taken), "image2");
+    }
+}
@@ -0,0 +1,276 @@
+use crate::meta::{ComfyEvent, Event, History, compare_node_ids};
+use std::collections::BTreeSet;
+
+/// Records the events of a prompt and cross-checks them against the expected
+/// execution lifecycle.
+///
+/// A prompt is expected to emit `execution_start`, then `executing` and
+/// `executed` events for its nodes, and finally one of `execution_success`,
+/// `execution_error` or `execution_interrupted`. Deviations usually point to
+/// lossy proxies or buffering gateways between the client and the server.
+///
+/// # Example
+///
+/// ```no_run
+/// # async fn run(prompt: &str) -> comfyui_client::ClientResult<()> {
+/// use comfyui_client::{ClientBuilder, diagnostics::PromptDiagnostics};
+/// use futures_util::StreamExt;
+///
+/// let (client, mut stream) = ClientBuilder::new("http://localhost:8188").build().await?;
+/// let status = client.post_prompt(prompt).await?;
+///
+/// let mut diagnostics = PromptDiagnostics::new(&status.prompt_id);
+/// while let Some(ev) = stream.next().await {
+///     if diagnostics.observe(&ev?) {
+///         break;
+///     }
+/// }
+/// for anomaly in client.diagnose(&diagnostics).await? {
+///     eprintln!("{anomaly:?}");
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct PromptDiagnostics {
+    prompt_id: String,
+    started: bool,
+    executing_seen: bool,
+    terminated: bool,
+    out_of_order: Vec<String>,
+    executed_nodes: BTreeSet<String>,
+    cached_nodes: BTreeSet<String>,
+}
+
+impl PromptDiagnostics {
+    /// Creates a new [`PromptDiagnostics`] for the given prompt.
+    pub fn new(prompt_id: &str) -> Self {
+        Self {
+            prompt_id: prompt_id.to_string(),
+            started: false,
+            executing_seen: false,
+            terminated: false,
+            out_of_order: Vec::new(),
+            executed_nodes: BTreeSet::new(),
+            cached_nodes: BTreeSet::new(),
+        }
+    }
+
+    /// Returns the ID of the diagnosed prompt.
+    pub fn prompt_id(&self) -> &str {
+        &self.prompt_id
+    }
+
+    /// Records an event, ignoring events of other prompts.
+    ///
+    /// # Parameters
+    ///
+    /// - `ev`: An [`Event`] received from the
+    ///   [`EventStream`](crate::EventStream).
+    ///
+    /// # Returns
+    ///
+    /// `true` if the event terminates the execution of the prompt, `false`
+    /// otherwise.
+    pub fn observe(&mut self, ev: &Event) -> bool {
+        let Event::Comfy(ev) = ev else {
+            return false;
+        };
+        let (prompt_id, event_type) = match ev {
+            ComfyEvent::ExecutionStart { data } => (&data.prompt_id, "execution_start"),
+            ComfyEvent::Executing { data } => (&data.prompt_id, "executing"),
+            ComfyEvent::Executed { data } => (&data.prompt_id, "executed"),
+            ComfyEvent::ExecutionCached { data } => (&data.prompt_id, "execution_cached"),
+            ComfyEvent::ExecutionSuccess { data } => (&data.prompt_id, "execution_success"),
+            ComfyEvent::ExecutionError { data } => (&data.prompt_id, "execution_error"),
+            ComfyEvent::ExecutionInterrupted { data } => (&data.prompt_id, "execution_interrupted"),
+            _ => return false,
+        };
+        if *prompt_id != self.prompt_id {
+            return false;
+        }
+
+        // The `executing` event without node marks the end of the execution,
+        // sent alone by older servers and after `execution_success` by newer
+        // ones.
+        let end_marker = matches!(ev, ComfyEvent::Executing { data } if data.node.is_none());
+        let expected_started = !matches!(ev, ComfyEvent::ExecutionStart { .. });
+        if (self.terminated && !end_marker) || self.started != expected_started {
+            self.out_of_order.push(event_type.to_string());
+        }
+
+        match ev {
+            ComfyEvent::ExecutionStart { .. } => self.started = true,
+            ComfyEvent::Executing { .. } if end_marker => {
+                let terminates = !self.terminated;
+                self.terminated = true;
+                return terminates;
+            }
+            ComfyEvent::Executing { .. } => self.executing_seen = true,
+            ComfyEvent::Executed { data } => {
+                self.executed_nodes.insert(data.node.clone());
+            }
+            ComfyEvent::ExecutionCached { data } => {
+                self.cached_nodes.extend(data.nodes.iter().cloned());
+            }
+            _ => {
+                self.terminated = true;
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Checks the recorded events for anomalies.
+    ///
+    /// Outputs of nodes reported by `execution_cached` are not expected to
+    /// have a matching `executed` event, as older servers don't resend them.
+    ///
+    /// # Parameters
+    ///
+    /// - `history`: The [`History`] of the prompt, if available.
+    ///
+    /// # Returns
+    ///
+    /// The detected [`LifecycleAnomaly`] list, empty if the lifecycle is
+    /// complete.
+    pub fn check(&self, history: Option<&History>) -> Vec<LifecycleAnomaly> {
+        let mut anomalies = Vec::new();
+        if !self.started {
+            anomalies.push(LifecycleAnomaly::MissingExecutionStart);
+        }
+        if !self.executing_seen {
+            anomalies.push(LifecycleAnomaly::MissingExecuting);
+        }
+        if !self.terminated {
+            anomalies.push(LifecycleAnomaly::MissingTermination);
+        }
+        anomalies.extend(
+            self.out_of_order
+                .iter()
+                .cloned()
+                .map(|event_type| LifecycleAnomaly::OutOfOrder { event_type }),
+        );
+        if let Some(history) = history {
+            let mut nodes = history
+                .outputs
+                .keys()
+                .filter(|node| {
+                    !self.executed_nodes.contains(*node) && !self.cached_nodes.contains(*node)
+                })
+                .cloned()
+                .collect::<Vec<_>>();
+            nodes.sort_by(|a, b| compare_node_ids(a, b));
+            anomalies.extend(
+                nodes
+                    .into_iter()
+                    .map(|node| LifecycleAnomaly::OutputWithoutExecuted { node }),
+            );
+        }
+        anomalies
+    }
+}
+
+/// An anomaly detected in the events received for a prompt.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum LifecycleAnomaly {
+    /// No `execution_start` event was received.
+    MissingExecutionStart,
+    /// No `executing` event was received.
+    MissingExecuting,
+    /// None of `execution_success`, `execution_error` or
+    /// `execution_interrupted` was received.
+    MissingTermination,
+    /// An event was received before `execution_start` or after the
+    /// execution terminated.
+    OutOfOrder {
+        /// The type of the event, e.g. `executing`.
+        event_type: String,
+    },
+    /// The history contains outputs of a node for which no `executed` event
+    /// was received.
+    OutputWithoutExecuted {
+        /// The identifier of the node.
+        node: String,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn event(value: serde_json::Value) -> Event {
+        Event::Comfy(serde_json::from_value(value).unwrap())
+    }
+
+    #[test]
+    fn test_diagnostics() {
+        let mut diagnostics = PromptDiagnostics::new("xxxxxx");
+
+        assert!(!diagnostics.observe(&event(json!({
+            "type": "executing",
+            "data": {"node": "5", "display_node": "5", "prompt_id": "xxxxxx"}
+        }))));
+        assert!(!diagnostics.observe(&event(json!({
+            "type": "execution_start",
+            "data": {"prompt_id": "other", "timestamp": 0}
+        }))));
+        assert!(diagnostics.observe(&event(json!({
+            "type": "execution_success",
+            "data": {"prompt_id": "xxxxxx"}
+        }))));
+
+        let history = serde_json::from_value::<History>(json!({
+            "outputs": {"5": {"images": []}}
+        }))
+        .unwrap();
+
+        assert_eq!(
+            diagnostics.check(Some(&history)),
+            [
+                LifecycleAnomaly::MissingExecutionStart,
+                LifecycleAnomaly::OutOfOrder {
+                    event_type: "executing".to_string()
+                },
+                LifecycleAnomaly::OutOfOrder {
+                    event_type: "execution_success".to_string()
+                },
+                LifecycleAnomaly::OutputWithoutExecuted {
+                    node: "5".to_string()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_diagnostics_end_marker() {
+        let start =
+            json!({"type": "execution_start", "data": {"prompt_id": "xxxxxx", "timestamp": 0}});
+        let executing = json!({
+            "type": "executing",
+            "data": {"node": "5", "display_node": "5", "prompt_id": "xxxxxx"}
+        });
+        let end_marker =
+            json!({"type": "executing", "data": {"node": null, "prompt_id": "xxxxxx"}});
+
+        // Older servers only send the end marker.
+        let mut diagnostics = PromptDiagnostics::new("xxxxxx");
+        assert!(!diagnostics.observe(&event(start.clone())));
+        assert!(!diagnostics.observe(&event(executing.clone())));
+        assert!(diagnostics.observe(&event(end_marker.clone())));
+        assert!(diagnostics.check(None).is_empty());
+
+        // Newer servers send it after `execution_success`.
+        let mut diagnostics = PromptDiagnostics::new("xxxxxx");
+        assert!(!diagnostics.observe(&event(start)));
+        assert!(!diagnostics.observe(&event(executing)));
+        assert!(diagnostics.observe(&event(json!({
+            "type": "execution_success",
+            "data": {"prompt_id": "xxxxxx"}
+        }))));
+        assert!(!diagnostics.observe(&event(end_marker)));
+        assert!(diagnostics.check(None).is_empty());
+    }
+}
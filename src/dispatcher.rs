@@ -0,0 +1,409 @@
+use crate::{
+    ClientError, ClientResult, EventStream,
+    errors::CancellationReason,
+    meta::{ComfyEvent, ConnectionEvent, Event, LagPolicy},
+};
+use futures_util::{Stream, StreamExt};
+use log::warn;
+use pin_project_lite::pin_project;
+use std::{
+    collections::HashMap,
+    pin::Pin,
+    sync::{
+        Arc, Mutex, MutexGuard, PoisonError,
+        atomic::{AtomicU64, Ordering},
+    },
+    task::{Context, Poll},
+};
+use tokio::sync::{
+    mpsc::{self, error::TrySendError},
+    oneshot,
+};
+use tokio_stream::wrappers::ReceiverStream;
+
+type Senders = HashMap<String, Subscriber>;
+
+/// The sending half of a [`PromptSubscription`].
+struct Subscriber {
+    tx: mpsc::Sender<ClientResult<ComfyEvent>>,
+    /// The number of events dropped since the subscription last reported
+    /// them.
+    lagged: Arc<AtomicU64>,
+}
+
+impl Subscriber {
+    /// Delivers an item without waiting, counting it as dropped if the buffer
+    /// of the subscription is full.
+    fn deliver(&self, item: ClientResult<ComfyEvent>) {
+        if let Err(TrySendError::Full(_)) = self.tx.try_send(item) {
+            self.lagged.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Dispatches the events of a single [`EventStream`] to subscriptions per
+/// prompt.
+///
+/// This allows driving many concurrent prompts over one websocket connection,
+/// each consumer only receiving the events of its own prompt.
+///
+/// # Example
+///
+/// ```no_run
+/// # async fn run(prompt: &str) -> comfyui_client::ClientResult<()> {
+/// use comfyui_client::{ClientBuilder, dispatcher::EventDispatcher, meta::PromptOptions};
+/// use futures_util::StreamExt;
+///
+/// let (client, stream) = ClientBuilder::new("http://localhost:8188").build().await?;
+/// let dispatcher = EventDispatcher::new(stream);
+///
+/// let options = PromptOptions {
+///     prompt_id: Some("my-prompt-id".to_string()),
+///     ..Default::default()
+/// };
+/// let mut subscription = dispatcher.subscribe("my-prompt-id");
+/// client.post_prompt_with_options(prompt, &options).await?;
+/// while let Some(ev) = subscription.next().await {
+///     println!("{:?}", ev?);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct EventDispatcher {
+    senders: Arc<Mutex<Senders>>,
+    channel_bound: usize,
+    lag_policy: LagPolicy,
+    _shutdown: oneshot::Sender<()>,
+}
+
+impl EventDispatcher {
+    /// Creates a new [`EventDispatcher`] consuming the given stream.
+    ///
+    /// Spawns a task dispatching the events, which stops once the dispatcher
+    /// is dropped or the stream ends.
+    ///
+    /// # Parameters
+    ///
+    /// - `stream`: The [`EventStream`] to dispatch the events of.
+    pub fn new(stream: EventStream) -> Self {
+        let senders = Arc::<Mutex<Senders>>::default();
+        let (shutdown_tx, shutdown_rx) = oneshot::channel();
+        tokio::spawn(Self::dispatch(stream, senders.clone(), shutdown_rx));
+        Self {
+            senders,
+            channel_bound: 100,
+            lag_policy: LagPolicy::default(),
+            _shutdown: shutdown_tx,
+        }
+    }
+
+    /// Sets the maximum number of events buffered per subscription.
+    ///
+    /// Once the buffer of a subscription is full, further events of its prompt
+    /// are dropped and reported according to the [`LagPolicy`], without
+    /// holding back the events of the other subscriptions. Applies to
+    /// subscriptions created afterwards.
+    ///
+    /// # Parameters
+    ///
+    /// - `channel_bound`: The maximum number of buffered events. Defaults to
+    ///   100.
+    ///
+    /// # Returns
+    ///
+    /// The updated [`EventDispatcher`].
+    pub fn channel_bound(mut self, channel_bound: usize) -> Self {
+        self.channel_bound = channel_bound;
+        self
+    }
+
+    /// Sets how subscriptions handle the events dropped because their buffer
+    /// was full, see [`channel_bound`](Self::channel_bound). Applies to
+    /// subscriptions created afterwards.
+    ///
+    /// # Parameters
+    ///
+    /// - `lag_policy`: The [`LagPolicy`]. Defaults to [`LagPolicy::Skip`].
+    ///
+    /// # Returns
+    ///
+    /// The updated [`EventDispatcher`].
+    pub fn lag_policy(mut self, lag_policy: LagPolicy) -> Self {
+        self.lag_policy = lag_policy;
+        self
+    }
+
+    /// Subscribes to the events of a prompt.
+    ///
+    /// Events received before subscribing are not delivered. To not miss any
+    /// event, subscribe before sending the prompt, using a caller-generated
+    /// prompt ID (see [`PromptOptions::prompt_id`]). Subscribing to the same
+    /// prompt again replaces the previous subscription, which then ends.
+    ///
+    /// [`PromptOptions::prompt_id`]: crate::meta::PromptOptions::prompt_id
+    ///
+    /// # Parameters
+    ///
+    /// - `prompt_id`: The ID of the prompt whose events to receive.
+    ///
+    /// # Returns
+    ///
+    /// A [`PromptSubscription`] streaming the events of the prompt.
+    pub fn subscribe(&self, prompt_id: &str) -> PromptSubscription {
+        let (tx, rx) = mpsc::channel(self.channel_bound.max(1));
+        let lagged = Arc::<AtomicU64>::default();
+        let subscriber = Subscriber {
+            tx,
+            lagged: lagged.clone(),
+        };
+        lock(&self.senders).insert(prompt_id.to_string(), subscriber);
+        PromptSubscription {
+            rx_stream: ReceiverStream::new(rx),
+            prompt_id: prompt_id.to_string(),
+            lagged,
+            lag_policy: self.lag_policy,
+        }
+    }
+
+    /// Dispatches the events of the stream until shut down or the stream
+    /// ends.
+    ///
+    /// Losing the connection ends all live subscriptions with a
+    /// [`ClientError::Cancelled`], as their events may be missed meanwhile.
+    async fn dispatch(
+        mut stream: EventStream, senders: Arc<Mutex<Senders>>, mut shutdown: oneshot::Receiver<()>,
+    ) {
+        loop {
+            let ev = tokio::select! {
+                ev = stream.next() => ev,
+                _ = &mut shutdown => return,
+            };
+            let Some(ev) = ev else {
+                break;
+            };
+
+            let (prompt_id, item) = match ev {
+                Ok(Event::Comfy(ev)) => match ev.prompt_id() {
+                    Some(prompt_id) => (prompt_id.to_string(), Ok(ev)),
+                    None => continue,
+                },
+                Ok(Event::Connection(
+                    ConnectionEvent::WSReceiveError(_)
+                    | ConnectionEvent::WSPingTimeout
+                    | ConnectionEvent::WSReconnectSuccess,
+                ))
+                | Err(ClientError::Tungstenite(_)) => {
+                    Self::cancel_all(&senders);
+                    continue;
+                }
+                Ok(_) => continue,
+                Err(ClientError::EventDecode(err)) => match err.raw["data"]["prompt_id"].as_str() {
+                    Some(prompt_id) => (prompt_id.to_string(), Err(err.into())),
+                    None => {
+                        warn!(err:%; "failed to decode event without prompt");
+                        continue;
+                    }
+                },
+                Err(err) => {
+                    warn!(err:%; "received error from event stream");
+                    continue;
+                }
+            };
+
+            let terminated = matches!(
+                item,
+                Ok(ComfyEvent::ExecutionSuccess { .. }
+                    | ComfyEvent::ExecutionError { .. }
+                    | ComfyEvent::ExecutionInterrupted { .. })
+            );
+            let mut senders = lock(&senders);
+            // Forget the subscriptions which have been dropped.
+            senders.retain(|_, subscriber| !subscriber.tx.is_closed());
+            if terminated {
+                if let Some(subscriber) = senders.remove(&prompt_id) {
+                    subscriber.deliver(item);
+                }
+            } else if let Some(subscriber) = senders.get(&prompt_id) {
+                subscriber.deliver(item);
+            }
+        }
+
+        // The stream ended, so no more events will be received.
+        Self::cancel_all(&senders);
+    }
+
+    /// Ends all live subscriptions with a [`ClientError::Cancelled`] because
+    /// the connection was lost.
+    fn cancel_all(senders: &Mutex<Senders>) {
+        for (_, subscriber) in lock(senders).drain() {
+            subscriber.deliver(Err(ClientError::Cancelled(
+                CancellationReason::ConnectionLost,
+            )));
+        }
+    }
+}
+
+pin_project! {
+    /// A stream of the events of a single prompt, obtained from
+    /// [`EventDispatcher::subscribe`].
+    ///
+    /// The stream ends after the `execution_success`, `execution_error` or
+    /// `execution_interrupted` event of the prompt. If the connection is lost
+    /// before, it yields a [`ClientError::Cancelled`] error and ends. Events
+    /// dropped because the buffer was full are handled according to the
+    /// [`LagPolicy`] of the [`EventDispatcher`] after the buffered ones, and
+    /// if the last event is dropped, the stream ends afterwards.
+    pub struct PromptSubscription {
+        #[pin]
+        rx_stream: ReceiverStream<ClientResult<ComfyEvent>>,
+        prompt_id: String,
+        lagged: Arc<AtomicU64>,
+        lag_policy: LagPolicy,
+    }
+}
+
+impl PromptSubscription {
+    /// Returns the ID of the subscribed prompt.
+    pub fn prompt_id(&self) -> &str {
+        &self.prompt_id
+    }
+}
+
+impl Stream for PromptSubscription {
+    type Item = ClientResult<ComfyEvent>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.project();
+        let poll = this.rx_stream.poll_next(cx);
+        if let Poll::Ready(Some(_)) = poll {
+            return poll;
+        }
+        // The dropped events are reported once the buffered ones, which were
+        // received before, have been consumed.
+        let skipped = this.lagged.swap(0, Ordering::Relaxed);
+        if skipped > 0 {
+            match this.lag_policy {
+                LagPolicy::Skip => {
+                    warn!(skipped, prompt_id:% = this.prompt_id; "subscription lagged behind, events skipped");
+                }
+                LagPolicy::Error => return Poll::Ready(Some(Err(ClientError::Lagged(skipped)))),
+            }
+        }
+        poll
+    }
+}
+
+/// Locks the senders, ignoring poisoning as they stay consistent.
+fn lock(senders: &Mutex<Senders>) -> MutexGuard<'_, Senders> {
+    senders.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn event(value: serde_json::Value) -> ClientResult<Event> {
+        Ok(Event::Comfy(serde_json::from_value(value).unwrap()))
+    }
+
+    #[tokio::test]
+    async fn test_dispatcher() {
+        let (tx, rx) = mpsc::channel(8);
+        let dispatcher = EventDispatcher::new(EventStream::from_receiver(rx));
+        let first = dispatcher.subscribe("first");
+        let second = dispatcher.subscribe("second");
+
+        for value in [
+            json!({"type": "execution_start", "data": {"prompt_id": "first", "timestamp": 0}}),
+            json!({"type": "execution_start", "data": {"prompt_id": "second", "timestamp": 0}}),
+            json!({"type": "execution_success", "data": {"prompt_id": "first"}}),
+            json!({"type": "executing", "data": {"node": "3", "display_node": "3", "prompt_id": "first"}}),
+        ] {
+            tx.send(event(value)).await.unwrap();
+        }
+
+        let events = first.collect::<Vec<_>>().await;
+        assert_eq!(events.len(), 2);
+        assert!(matches!(events[0], Ok(ComfyEvent::ExecutionStart { .. })));
+        assert!(matches!(events[1], Ok(ComfyEvent::ExecutionSuccess { .. })));
+
+        drop(tx);
+        let events = second.collect::<Vec<_>>().await;
+        assert_eq!(events.len(), 2);
+        assert!(matches!(events[0], Ok(ComfyEvent::ExecutionStart { .. })));
+        assert!(matches!(
+            events[1],
+            Err(ClientError::Cancelled(CancellationReason::ConnectionLost))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_dispatcher_bounded() {
+        let (tx, rx) = mpsc::channel(8);
+        let dispatcher = EventDispatcher::new(EventStream::from_receiver(rx))
+            .channel_bound(1)
+            .lag_policy(LagPolicy::Error);
+        let first = dispatcher.subscribe("first");
+        let second = dispatcher.subscribe("second");
+        drop(dispatcher.subscribe("dropped"));
+
+        for node in ["1", "2", "3"] {
+            tx.send(event(json!({
+                "type": "executing",
+                "data": {"node": node, "display_node": node, "prompt_id": "first"}
+            })))
+            .await
+            .unwrap();
+        }
+        for prompt_id in ["first", "second"] {
+            tx.send(event(
+                json!({"type": "execution_success", "data": {"prompt_id": prompt_id}}),
+            ))
+            .await
+            .unwrap();
+        }
+
+        // The full subscription doesn't hold back the other one.
+        let events = second.collect::<Vec<_>>().await;
+        assert_eq!(events.len(), 1);
+        assert!(matches!(events[0], Ok(ComfyEvent::ExecutionSuccess { .. })));
+
+        // The events overflowing the full subscription are reported after the
+        // buffered one.
+        let events = first.collect::<Vec<_>>().await;
+        assert_eq!(events.len(), 2);
+        assert!(matches!(
+            &events[0],
+            Ok(ComfyEvent::Executing { data }) if data.node.as_deref() == Some("1")
+        ));
+        assert!(matches!(events[1], Err(ClientError::Lagged(3))));
+        // The dropped subscription has been forgotten by the dispatching.
+        assert!(lock(&dispatcher.senders).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_dispatcher_connection_lost() {
+        let (tx, rx) = mpsc::channel(8);
+        let dispatcher = EventDispatcher::new(EventStream::from_receiver(rx));
+        let first = dispatcher.subscribe("first");
+
+        tx.send(event(
+            json!({"type": "execution_start", "data": {"prompt_id": "first", "timestamp": 0}}),
+        ))
+        .await
+        .unwrap();
+        tx.send(Ok(Event::Connection(ConnectionEvent::WSPingTimeout)))
+            .await
+            .unwrap();
+
+        // The subscription ends although the stream goes on.
+        let events = first.collect::<Vec<_>>().await;
+        assert_eq!(events.len(), 2);
+        assert!(matches!(
+            events[1],
+            Err(ClientError::Cancelled(CancellationReason::ConnectionLost))
+        ));
+        assert!(!tx.is_closed());
+    }
+}
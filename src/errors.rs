@@ -1,5 +1,14 @@
-use reqwest::StatusCode;
+use crate::{
+    meta::{ExecutionErrorEventData, ExecutionInterruptedEventData, ModelMatch, compare_node_ids},
+    template::ParamType,
+};
+use bytes::Bytes;
+use reqwest::{
+    Method, StatusCode, Url,
+    header::{CONTENT_TYPE, HeaderMap, RETRY_AFTER},
+};
 use serde_json::Value;
+use std::{fmt, time::Duration};
 use tokio_tungstenite::tungstenite;
 
 /// Type alias for the result of client operations.
@@ -13,17 +22,53 @@ pub enum ClientError {
     UrlParse(#[from] url::ParseError),
 
     /// Error that occurs during a reqwest operation.
+    ///
+    /// Errors of requests sent by the client are reported as
+    /// [`ClientError::Request`] instead, carrying the context of the request.
     #[error(transparent)]
     Reqwest(#[from] reqwest::Error),
 
+    /// Error that occurs while sending an HTTP request or decoding its
+    /// response, along with the context of the request.
+    #[error("{context}: {source}")]
+    Request {
+        /// The underlying error.
+        #[source]
+        source: reqwest::Error,
+        /// The context of the request.
+        context: Box<RequestContext>,
+    },
+
     /// Error that occurs during a tungstenite operation.
     #[error(transparent)]
     Tungstenite(#[from] tungstenite::Error),
 
+    /// Error that occurs during an I/O operation.
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
     /// Error that occurs during a serde_json operation.
     #[error(transparent)]
     SerdeJson(#[from] serde_json::Error),
 
+    /// Error that occurs when a header value contains invalid characters.
+    #[error(transparent)]
+    InvalidHeaderValue(#[from] reqwest::header::InvalidHeaderValue),
+
+    /// Error that occurs when a header name contains invalid characters.
+    #[error(transparent)]
+    InvalidHeaderName(#[from] reqwest::header::InvalidHeaderName),
+
+    /// Error that occurs when decoding or encoding an image.
+    #[cfg(feature = "image")]
+    #[error(transparent)]
+    Image(#[from] image::ImageError),
+
+    /// Error that occurs when sending a websocket message while the websocket
+    /// isn't connected.
+    #[error("websocket not connected")]
+    NotConnected,
+
     /// Error that occurs when setting the websocket scheme.
     #[error("set websocket scheme failed")]
     SetWsScheme,
@@ -31,16 +76,404 @@ pub enum ClientError {
     /// Error that occurs during an API operation.
     #[error(transparent)]
     Api(#[from] ApiError),
+
+    /// Error that occurs when the execution of a prompt fails.
+    #[error("execution of prompt {} failed: {}", .0.prompt_id, .0.exception_message)]
+    Execution(Box<ExecutionErrorEventData>),
+
+    /// Error that occurs when the execution of a prompt is cancelled before
+    /// completion.
+    #[error("execution cancelled: {0}")]
+    Cancelled(CancellationReason),
+
+    /// Error that occurs when the history of a prompt is not found.
+    #[error("history of prompt {0} not found")]
+    HistoryNotFound(String),
+
+    /// Error that occurs when a node is not found in a workflow.
+    #[error("node {0} not found")]
+    NodeNotFound(String),
+
+    /// Error that occurs when converting a workflow in the UI format which
+    /// isn't valid or contains a node whose class is unknown.
+    #[error("invalid UI workflow: {0}")]
+    InvalidUiWorkflow(String),
+
+    /// Error that occurs when a node has no seed input to set.
+    #[error("node {0} has no seed input")]
+    NoSeedInput(String),
+
+    /// Error that occurs when a node has no file output to bind.
+    #[error("node {0} has no file output")]
+    MissingOutput(String),
+
+    /// Error that occurs when a binding of a pipeline stage can't be applied,
+    /// e.g. because the stage has no previous one.
+    #[error("invalid binding: {0}")]
+    InvalidBinding(String),
+
+    /// Error that occurs when an
+    /// [`EventSubscription`](crate::EventSubscription) lagged behind and
+    /// missed the given number of events.
+    #[error("subscription lagged behind, {0} events skipped")]
+    Lagged(u64),
+
+    /// Error that occurs when decoding a websocket event in strict mode.
+    #[error(transparent)]
+    EventDecode(#[from] EventDecodeError),
+
+    /// Error that occurs when parsing or rendering a
+    /// [`PromptTemplate`](crate::template::PromptTemplate).
+    #[error(transparent)]
+    Template(#[from] TemplateError),
+}
+
+impl ClientError {
+    /// Classifies the error as transient or permanent, e.g. to decide
+    /// whether to retry the failed operation.
+    ///
+    /// Transient errors are failures of the transport, such as refused or
+    /// reset connections, timeouts and closed websockets, as well as the
+    /// HTTP status codes `408 Request Timeout`, `429 Too Many Requests`,
+    /// `502 Bad Gateway`, `503 Service Unavailable` and
+    /// `504 Gateway Timeout`. Losing the connection while waiting for a
+    /// prompt is transient too. All other errors, e.g. validation errors,
+    /// `404 Not Found` or a failed execution, are permanent.
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::Reqwest(err) | Self::Request { source: err, .. } => ErrorKind::of_reqwest(err),
+            Self::Tungstenite(err) => match err {
+                tungstenite::Error::ConnectionClosed
+                | tungstenite::Error::AlreadyClosed
+                | tungstenite::Error::Protocol(_) => ErrorKind::Transient,
+                tungstenite::Error::Io(err) => ErrorKind::of_io(err),
+                tungstenite::Error::Http(resp) => ErrorKind::of_status(resp.status()),
+                _ => ErrorKind::Permanent,
+            },
+            Self::Io(err) => ErrorKind::of_io(err),
+            Self::Api(err) => ErrorKind::of_status(err.status),
+            Self::NotConnected | Self::Cancelled(CancellationReason::ConnectionLost) => {
+                ErrorKind::Transient
+            }
+            _ => ErrorKind::Permanent,
+        }
+    }
+
+    /// Returns the context of the failed HTTP request, if the error
+    /// occurred during one.
+    pub fn context(&self) -> Option<&RequestContext> {
+        match self {
+            Self::Request { context, .. } => Some(context),
+            Self::Api(err) => err.context.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Returns the [`ApiError`], if the server responded with an error
+    /// status.
+    pub fn api_error(&self) -> Option<&ApiError> {
+        match self {
+            Self::Api(err) => Some(err),
+            _ => None,
+        }
+    }
+
+    /// Attaches the context of the failed request to a [`ClientError::Api`],
+    /// or turns a [`ClientError::Reqwest`] into a [`ClientError::Request`],
+    /// leaving other errors untouched.
+    pub(crate) fn with_context(mut self, context: RequestContext) -> Self {
+        match self {
+            Self::Reqwest(source) | Self::Request { source, .. } => Self::Request {
+                source,
+                context: Box::new(context),
+            },
+            Self::Api(ref mut err) => {
+                err.context = Some(Box::new(context));
+                self
+            }
+            _ => self,
+        }
+    }
+
+    /// Records the prompt a failed request related to in its context.
+    pub(crate) fn with_prompt_id(mut self, prompt_id: &str) -> Self {
+        let context = match &mut self {
+            Self::Request { context, .. } => Some(context),
+            Self::Api(err) => err.context.as_mut(),
+            _ => None,
+        };
+        if let Some(context) = context {
+            context.prompt_id = Some(prompt_id.to_string());
+        }
+        self
+    }
+
+    /// Checks whether the error is transient, so that retrying the failed
+    /// operation may succeed. See [`ClientError::kind`].
+    pub fn is_retryable(&self) -> bool {
+        self.kind() == ErrorKind::Transient
+    }
+}
+
+/// The context of an HTTP request, attached to its errors via
+/// [`ClientError::context`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RequestContext {
+    /// The method of the request.
+    pub method: Method,
+    /// The full URL of the request.
+    pub url: Url,
+    /// The ID of the prompt the request related to, if any.
+    pub prompt_id: Option<String>,
+    /// The `X-Request-Id` header of the request, if set via
+    /// [`ClientBuilder::request_id`](crate::ClientBuilder::request_id).
+    pub request_id: Option<String>,
+}
+
+impl fmt::Display for RequestContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.method, self.url)?;
+        if let Some(prompt_id) = &self.prompt_id {
+            write!(f, " (prompt {prompt_id})")?;
+        }
+        Ok(())
+    }
+}
+
+/// The classification of a [`ClientError`], see [`ClientError::kind`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// A failure of the transport or an overloaded server, which may
+    /// succeed when retried.
+    Transient,
+    /// A failure caused by the operation itself, which fails again when
+    /// retried.
+    Permanent,
+}
+
+impl ErrorKind {
+    /// Classifies an HTTP status code of a failed response.
+    pub(crate) fn of_status(status: StatusCode) -> Self {
+        match status {
+            StatusCode::REQUEST_TIMEOUT
+            | StatusCode::TOO_MANY_REQUESTS
+            | StatusCode::BAD_GATEWAY
+            | StatusCode::SERVICE_UNAVAILABLE
+            | StatusCode::GATEWAY_TIMEOUT => Self::Transient,
+            _ => Self::Permanent,
+        }
+    }
+
+    /// Classifies an error of the HTTP client.
+    pub(crate) fn of_reqwest(err: &reqwest::Error) -> Self {
+        match err.status() {
+            Some(status) => Self::of_status(status),
+            None if err.is_connect() || err.is_timeout() || err.is_request() || err.is_body() => {
+                Self::Transient
+            }
+            None => Self::Permanent,
+        }
+    }
+
+    /// Classifies an I/O error.
+    fn of_io(err: &std::io::Error) -> Self {
+        use std::io::ErrorKind::*;
+
+        match err.kind() {
+            ConnectionRefused | ConnectionReset | ConnectionAborted | NotConnected | BrokenPipe
+            | TimedOut | Interrupted | UnexpectedEof | WouldBlock => Self::Transient,
+            _ => Self::Permanent,
+        }
+    }
+}
+
+/// The reason why the execution of a prompt ended before completion.
+///
+/// Allows retry logic to distinguish a deliberate cancellation from a failure
+/// of the infrastructure.
+#[derive(thiserror::Error, Debug)]
+#[non_exhaustive]
+pub enum CancellationReason {
+    /// The execution was interrupted on the server, e.g. by a user.
+    #[error("prompt {} interrupted at node {}", .0.prompt_id, .0.node_id)]
+    Interrupted(ExecutionInterruptedEventData),
+
+    /// The connection to the server was lost while waiting for the execution
+    /// to terminate.
+    #[error("connection lost")]
+    ConnectionLost,
+
+    /// The prompt was deleted from the queue before it started, without
+    /// reporting a terminal event.
+    #[error("prompt deleted from the queue")]
+    QueueDeleted,
+
+    /// The client gave up waiting after the given duration.
+    #[error("timed out after {0:?}")]
+    Timeout(Duration),
 }
 
 /// Error that occurs during an API operation.
+///
+/// The `error` of ComfyUI error bodies is parsed into [`ApiError::error`],
+/// and their `node_errors` are available via
+/// [`ApiError::validation_errors`], like
+/// [`PromptStatus::validation_errors`](crate::meta::PromptStatus::validation_errors).
+/// The body is kept in [`ApiError::body`] and, untouched, in
+/// [`ApiError::raw_body`]. The
+/// headers allow handling errors of gateways or authentication layers in
+/// front of the server, e.g. via [`ApiError::retry_after`].
 #[derive(thiserror::Error, Debug)]
-#[error("api error")]
+#[error(
+    "{}api error ({status}){}",
+    .context.as_ref().map(|context| format!("{context}: ")).unwrap_or_default(),
+    .error.as_ref().map(|error| format!(": {error}")).unwrap_or_default(),
+)]
+#[non_exhaustive]
 pub struct ApiError {
     /// The HTTP status code of the API response.
     pub status: StatusCode,
+    /// The parsed `error` of the body, if present.
+    pub error: Option<ApiErrorInfo>,
     /// The body of the API response.
     pub body: ApiBody,
+    /// The headers of the API response.
+    pub headers: HeaderMap,
+    /// The untouched bytes of the body of the API response.
+    pub raw_body: Bytes,
+    /// The context of the failed request, if known.
+    pub context: Option<Box<RequestContext>>,
+}
+
+impl ApiError {
+    /// Creates an [`ApiError`] from a response, parsing the body as JSON if
+    /// possible and the ComfyUI error schema from it.
+    ///
+    /// # Parameters
+    ///
+    /// - `status`: The HTTP status code of the API response.
+    /// - `headers`: The headers of the API response.
+    /// - `raw_body`: The body of the API response.
+    ///
+    /// # Returns
+    ///
+    /// The [`ApiError`], whose parsed fields are empty if the body doesn't
+    /// follow the schema.
+    pub fn new(status: StatusCode, headers: HeaderMap, raw_body: Bytes) -> Self {
+        let body = match serde_json::from_slice::<Value>(&raw_body) {
+            Ok(value) => ApiBody::Json(value),
+            Err(_) => ApiBody::Text(String::from_utf8_lossy(&raw_body).into_owned()),
+        };
+        let error = match &body {
+            ApiBody::Json(value) => ApiErrorInfo::parse(&value["error"]),
+            ApiBody::Text(_) => None,
+        };
+        Self {
+            status,
+            error,
+            body,
+            headers,
+            raw_body,
+            context: None,
+        }
+    }
+
+    /// Returns the `Content-Type` header of the response, if any.
+    pub fn content_type(&self) -> Option<&str> {
+        self.headers.get(CONTENT_TYPE)?.to_str().ok()
+    }
+
+    /// Returns the delay requested by the `Retry-After` header of the
+    /// response, if it is given in seconds.
+    pub fn retry_after(&self) -> Option<Duration> {
+        let value = self.headers.get(RETRY_AFTER)?.to_str().ok()?;
+        value.trim().parse().ok().map(Duration::from_secs)
+    }
+
+    /// Returns the type of the error, e.g.
+    /// `prompt_outputs_failed_validation`, if the body carries one.
+    pub fn error_type(&self) -> Option<&str> {
+        self.error
+            .as_ref()
+            .map(|error| error.error_type.as_str())
+            .filter(|error_type| !error_type.is_empty())
+    }
+
+    /// Returns the inputs that failed validation because the requested value,
+    /// such as a model or input file, is not available on the server.
+    ///
+    /// Returns an empty list if the body isn't a prompt validation failure.
+    pub fn missing_dependencies(&self) -> Vec<MissingDependency> {
+        match &self.body {
+            ApiBody::Json(value) => match value["node_errors"].as_object() {
+                Some(node_errors) => MissingDependency::parse_node_errors(node_errors),
+                None => Vec::new(),
+            },
+            ApiBody::Text(_) => Vec::new(),
+        }
+    }
+
+    /// Returns the validation errors of the nodes of a rejected prompt,
+    /// ordered by node identifier.
+    ///
+    /// Returns an empty list if the body isn't a prompt validation failure.
+    pub fn validation_errors(&self) -> Vec<ValidationError> {
+        match &self.body {
+            ApiBody::Json(value) => match value["node_errors"].as_object() {
+                Some(node_errors) => ValidationError::parse_node_errors(node_errors),
+                None => Vec::new(),
+            },
+            ApiBody::Text(_) => Vec::new(),
+        }
+    }
+}
+
+/// The `error` of a ComfyUI error body.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ApiErrorInfo {
+    /// The type of the error, e.g. `prompt_outputs_failed_validation` or
+    /// `invalid_prompt`. Empty if the server only sent a message.
+    pub error_type: String,
+    /// The message of the error.
+    pub message: String,
+    /// The details of the error.
+    pub details: String,
+    /// Additional information about the error.
+    pub extra_info: Value,
+}
+
+impl ApiErrorInfo {
+    /// Parses the `error` of a body, which is either an object or, on some
+    /// endpoints, a plain message.
+    fn parse(error: &Value) -> Option<Self> {
+        let text = |value: &Value| value.as_str().unwrap_or_default().to_string();
+        match error {
+            Value::String(message) => Some(Self {
+                error_type: String::new(),
+                message: message.clone(),
+                details: String::new(),
+                extra_info: Value::Null,
+            }),
+            Value::Object(_) => Some(Self {
+                error_type: text(&error["type"]),
+                message: text(&error["message"]),
+                details: text(&error["details"]),
+                extra_info: error["extra_info"].clone(),
+            }),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ApiErrorInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)?;
+        if !self.details.is_empty() {
+            write!(f, ": {}", self.details)?;
+        }
+        Ok(())
+    }
 }
 
 /// The body of an API response.
@@ -51,3 +484,369 @@ pub enum ApiBody {
     /// Text body.
     Text(String),
 }
+
+/// Error that occurs when parsing or rendering a
+/// [`PromptTemplate`](crate::template::PromptTemplate).
+#[derive(thiserror::Error, Debug)]
+#[non_exhaustive]
+pub enum TemplateError {
+    /// A placeholder declares an unknown type, or a parameter is declared
+    /// with conflicting types.
+    #[error("invalid placeholder: {0}")]
+    InvalidPlaceholder(String),
+
+    /// No value is given for a parameter of the template.
+    #[error("missing value of parameter {0}")]
+    MissingParameter(String),
+
+    /// The value of a parameter doesn't match its declared type.
+    #[error("value {value} of parameter {name} is not of type {expected}")]
+    TypeMismatch {
+        /// The name of the parameter.
+        name: String,
+        /// The declared type of the parameter.
+        expected: ParamType,
+        /// The given value.
+        value: Value,
+    },
+}
+
+/// Error that occurs when a websocket event cannot be decoded.
+///
+/// Only surfaced when strict event decoding is enabled via
+/// [`ClientBuilder::strict_event_decoding`](crate::ClientBuilder::strict_event_decoding).
+#[derive(thiserror::Error, Debug)]
+#[error("decode event of type {type_field:?} failed: {serde_error}")]
+pub struct EventDecodeError {
+    /// The `type` field of the event, if present.
+    pub type_field: Option<String>,
+    /// The underlying deserialization error.
+    #[source]
+    pub serde_error: serde_json::Error,
+    /// The raw JSON payload of the event.
+    pub raw: Value,
+}
+
+/// The validation errors of a node of a prompt, as reported in the
+/// `node_errors` of the server.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ValidationError {
+    /// The identifier of the invalid node.
+    pub node_id: String,
+    /// The class type of the invalid node.
+    pub class_type: String,
+    /// The errors of the node.
+    pub errors: Vec<NodeInputError>,
+}
+
+impl ValidationError {
+    /// Parses the validation errors from the `node_errors` of a prompt,
+    /// ordered by node identifier.
+    pub(crate) fn parse_node_errors<'a>(
+        node_errors: impl IntoIterator<Item = (&'a String, &'a Value)>,
+    ) -> Vec<Self> {
+        let mut validation_errors = node_errors
+            .into_iter()
+            .map(|(node_id, node_error)| {
+                let errors = node_error["errors"].as_array().map(Vec::as_slice);
+                Self {
+                    node_id: node_id.clone(),
+                    class_type: node_error["class_type"]
+                        .as_str()
+                        .unwrap_or_default()
+                        .to_string(),
+                    errors: errors
+                        .unwrap_or_default()
+                        .iter()
+                        .map(NodeInputError::parse)
+                        .collect(),
+                }
+            })
+            .collect::<Vec<_>>();
+        validation_errors.sort_by(|a, b| compare_node_ids(&a.node_id, &b.node_id));
+        validation_errors
+    }
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "node {} ({})", self.node_id, self.class_type)?;
+        for (i, error) in self.errors.iter().enumerate() {
+            f.write_str(if i == 0 { ": " } else { "; " })?;
+            write!(f, "{error}")?;
+        }
+        Ok(())
+    }
+}
+
+/// A validation error of a node, usually concerning one of its inputs.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NodeInputError {
+    /// The name of the invalid input, if the error concerns an input.
+    pub input: Option<String>,
+    /// The message of the error, e.g. `Value not in list`.
+    pub message: String,
+    /// The details of the error, e.g. the expected and received values.
+    pub details: String,
+}
+
+impl NodeInputError {
+    fn parse(error: &Value) -> Self {
+        let text = |value: &Value| value.as_str().unwrap_or_default().to_string();
+        Self {
+            input: error["extra_info"]["input_name"]
+                .as_str()
+                .map(ToOwned::to_owned),
+            message: text(&error["message"]),
+            details: text(&error["details"]),
+        }
+    }
+}
+
+impl fmt::Display for NodeInputError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)?;
+        if !self.details.is_empty() {
+            write!(f, ": {}", self.details)?;
+        }
+        Ok(())
+    }
+}
+
+/// An input whose value is not among the values available on the server.
+///
+/// ComfyUI reports these as `value_not_in_list` validation errors, which
+/// typically occur when a workflow references a model or input file that
+/// doesn't exist on the server.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MissingDependency {
+    /// The identifier of the node with the invalid input.
+    pub node: String,
+    /// The name of the invalid input.
+    pub input: String,
+    /// The value requested by the workflow.
+    pub expected: String,
+    /// The values available on the server.
+    pub available: Vec<String>,
+}
+
+impl MissingDependency {
+    /// Finds the available value closest to the expected one, e.g. to replace
+    /// a model name that is spelled differently on the server.
+    ///
+    /// See [`ModelMatch::find`] for details on the matching.
+    pub fn closest_available(&self, min_confidence: f64) -> Option<ModelMatch> {
+        ModelMatch::find(
+            &self.expected,
+            self.available.iter().map(String::as_str),
+            min_confidence,
+        )
+    }
+
+    /// Parses the missing dependencies from the `node_errors` of a prompt,
+    /// ordered by node identifier.
+    pub(crate) fn parse_node_errors<'a>(
+        node_errors: impl IntoIterator<Item = (&'a String, &'a Value)>,
+    ) -> Vec<Self> {
+        let mut dependencies = node_errors
+            .into_iter()
+            .flat_map(|(node, node_error)| {
+                let errors = node_error["errors"].as_array().map(Vec::as_slice);
+                errors
+                    .unwrap_or_default()
+                    .iter()
+                    .filter(|error| error["type"] == "value_not_in_list")
+                    .filter_map(move |error| {
+                        let extra_info = &error["extra_info"];
+                        let expected = match &extra_info["received_value"] {
+                            Value::String(value) => value.clone(),
+                            value => value.to_string(),
+                        };
+                        let available = extra_info["input_config"][0]
+                            .as_array()
+                            .map(|values| {
+                                values
+                                    .iter()
+                                    .filter_map(|value| value.as_str().map(ToOwned::to_owned))
+                                    .collect()
+                            })
+                            .unwrap_or_default();
+                        Some(Self {
+                            node: node.clone(),
+                            input: extra_info["input_name"].as_str()?.to_string(),
+                            expected,
+                            available,
+                        })
+                    })
+            })
+            .collect::<Vec<_>>();
+        dependencies.sort_by(|a, b| compare_node_ids(&a.node, &b.node));
+        dependencies
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_missing_dependencies() {
+        let err = ApiError::new(
+            StatusCode::BAD_REQUEST,
+            HeaderMap::new(),
+            Bytes::from(json!({
+                "error": {
+                    "type": "prompt_outputs_failed_validation",
+                    "message": "Prompt outputs failed validation",
+                    "details": "",
+                    "extra_info": {}
+                },
+                "node_errors": {
+                    "30": {
+                        "errors": [{
+                            "type": "value_not_in_list",
+                            "message": "Value not in list",
+                            "details": "ckpt_name: 'flux1-dev.safetensors' not in ['flux1-dev-fp8.safetensors']",
+                            "extra_info": {
+                                "input_name": "ckpt_name",
+                                "input_config": [["flux1-dev-fp8.safetensors"]],
+                                "received_value": "flux1-dev.safetensors"
+                            }
+                        }, {
+                            "type": "required_input_missing",
+                            "message": "Required input is missing",
+                            "details": "clip",
+                            "extra_info": {"input_name": "clip"}
+                        }],
+                        "dependent_outputs": ["9"],
+                        "class_type": "CheckpointLoaderSimple"
+                    }
+                }
+            })
+            .to_string()),
+        );
+        assert_eq!(err.error_type(), Some("prompt_outputs_failed_validation"));
+        assert_eq!(
+            err.to_string(),
+            "api error (400 Bad Request): Prompt outputs failed validation"
+        );
+
+        let validation_errors = err.validation_errors();
+        assert_eq!(
+            validation_errors,
+            [ValidationError {
+                node_id: "30".to_string(),
+                class_type: "CheckpointLoaderSimple".to_string(),
+                errors: vec![
+                    NodeInputError {
+                        input: Some("ckpt_name".to_string()),
+                        message: "Value not in list".to_string(),
+                        details: "ckpt_name: 'flux1-dev.safetensors' not in \
+                                  ['flux1-dev-fp8.safetensors']"
+                            .to_string(),
+                    },
+                    NodeInputError {
+                        input: Some("clip".to_string()),
+                        message: "Required input is missing".to_string(),
+                        details: "clip".to_string(),
+                    },
+                ],
+            }]
+        );
+        assert_eq!(
+            validation_errors[0].to_string(),
+            "node 30 (CheckpointLoaderSimple): Value not in list: ckpt_name: \
+             'flux1-dev.safetensors' not in ['flux1-dev-fp8.safetensors']; Required input is \
+             missing: clip"
+        );
+        assert_eq!(
+            err.missing_dependencies(),
+            [MissingDependency {
+                node: "30".to_string(),
+                input: "ckpt_name".to_string(),
+                expected: "flux1-dev.safetensors".to_string(),
+                available: vec!["flux1-dev-fp8.safetensors".to_string()],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_api_error_schema() {
+        let err = ApiError::new(
+            StatusCode::NOT_FOUND,
+            HeaderMap::new(),
+            Bytes::from_static(br#"{"error": "no such prompt"}"#),
+        );
+        assert_eq!(err.error_type(), None);
+        assert_eq!(err.error.as_ref().unwrap().message, "no such prompt");
+        assert!(err.validation_errors().is_empty());
+
+        let headers = HeaderMap::from_iter([
+            (CONTENT_TYPE, "text/html".parse().unwrap()),
+            (RETRY_AFTER, "30".parse().unwrap()),
+        ]);
+        let err = ApiError::new(
+            StatusCode::BAD_GATEWAY,
+            headers,
+            Bytes::from_static(b"<html>Bad Gateway</html>"),
+        );
+        assert!(err.error.is_none());
+        assert!(matches!(&err.body, ApiBody::Text(text) if text == "<html>Bad Gateway</html>"));
+        assert_eq!(err.raw_body, b"<html>Bad Gateway</html>"[..]);
+        assert_eq!(err.content_type(), Some("text/html"));
+        assert_eq!(err.retry_after(), Some(Duration::from_secs(30)));
+        assert_eq!(err.to_string(), "api error (502 Bad Gateway)");
+    }
+
+    #[test]
+    fn test_error_kind() {
+        let api_error =
+            |status| ClientError::from(ApiError::new(status, HeaderMap::new(), Bytes::new()));
+        assert!(api_error(StatusCode::SERVICE_UNAVAILABLE).is_retryable());
+        assert!(api_error(StatusCode::TOO_MANY_REQUESTS).is_retryable());
+        assert!(!api_error(StatusCode::NOT_FOUND).is_retryable());
+        assert_eq!(
+            api_error(StatusCode::BAD_REQUEST).kind(),
+            ErrorKind::Permanent
+        );
+
+        let io_error = |kind| ClientError::Io(std::io::Error::from(kind));
+        assert!(io_error(std::io::ErrorKind::ConnectionReset).is_retryable());
+        assert!(!io_error(std::io::ErrorKind::NotFound).is_retryable());
+
+        assert!(ClientError::Tungstenite(tungstenite::Error::ConnectionClosed).is_retryable());
+        assert!(ClientError::NotConnected.is_retryable());
+        assert!(ClientError::Cancelled(CancellationReason::ConnectionLost).is_retryable());
+        assert!(!ClientError::HistoryNotFound("id".to_string()).is_retryable());
+    }
+
+    #[test]
+    fn test_request_context() {
+        let context = RequestContext {
+            method: Method::GET,
+            url: Url::parse("http://localhost:8188/history/abc").unwrap(),
+            prompt_id: None,
+            request_id: None,
+        };
+        let err = ClientError::from(ApiError::new(
+            StatusCode::SERVICE_UNAVAILABLE,
+            HeaderMap::new(),
+            Bytes::new(),
+        ))
+        .with_context(context)
+        .with_prompt_id("abc");
+        assert_eq!(
+            err.to_string(),
+            "GET http://localhost:8188/history/abc (prompt abc): api error (503 Service \
+             Unavailable)"
+        );
+        assert_eq!(err.context().unwrap().prompt_id.as_deref(), Some("abc"));
+        assert!(matches!(&err, ClientError::Api(err) if err.context.is_some()));
+        assert_eq!(
+            err.api_error().unwrap().status,
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+        assert!(err.is_retryable());
+    }
+}
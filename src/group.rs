@@ -0,0 +1,809 @@
+use crate::{
+    ClientError, ClientResult, ComfyUIClient, EventStream, PromptCheck,
+    errors::CancellationReason,
+    meta::{
+        ComfyEvent, ConnectionEvent, Event, History, NodeState, OutputCollector, Prompt,
+        PromptStatus, WorkflowOutputs,
+    },
+};
+use futures_util::{
+    StreamExt,
+    future::{join_all, pending, try_join_all},
+};
+use log::warn;
+use std::time::Duration;
+use tokio::time::sleep;
+
+/// A handle to a prompt sent to the server, tracked by a [`JobGroup`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct JobHandle {
+    prompt_id: String,
+    number: i64,
+}
+
+impl JobHandle {
+    /// Creates a handle to an already sent prompt.
+    ///
+    /// # Parameters
+    ///
+    /// - `prompt_id`: The ID of the prompt.
+    /// - `number`: The number of the prompt in the queue.
+    pub fn new(prompt_id: impl Into<String>, number: i64) -> Self {
+        Self {
+            prompt_id: prompt_id.into(),
+            number,
+        }
+    }
+
+    /// Returns the ID of the prompt.
+    pub fn prompt_id(&self) -> &str {
+        &self.prompt_id
+    }
+
+    /// Returns the number of the prompt in the queue.
+    pub fn number(&self) -> i64 {
+        self.number
+    }
+}
+
+impl From<PromptStatus> for JobHandle {
+    fn from(status: PromptStatus) -> Self {
+        Self::new(status.prompt_id, status.number)
+    }
+}
+
+/// A group of prompts awaited together, like the images of a batch
+/// generation.
+///
+/// The group follows the events of all its prompts on a single
+/// [`EventStream`], exposes their aggregate progress and resolves once all of
+/// them have terminated. With [`fail_fast`](Self::fail_fast) enabled, the
+/// group resolves on the first failure instead and cancels the remaining
+/// prompts.
+///
+/// # Example
+///
+/// ```no_run
+/// # async fn run(prompts: &[&str]) -> comfyui_client::ClientResult<()> {
+/// use comfyui_client::{ClientBuilder, group::JobGroup};
+///
+/// let (client, mut stream) = ClientBuilder::new("http://localhost:8188").build().await?;
+/// let mut group = JobGroup::new().fail_fast(true);
+/// for prompt in prompts {
+///     group.submit(&client, *prompt).await?;
+/// }
+/// for job in group.wait(&client, &mut stream, None).await? {
+///     for file in job.result?.images() {
+///         let bytes = client.get_view(file).await?;
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Default)]
+pub struct JobGroup {
+    jobs: Vec<Job>,
+    fail_fast: bool,
+    first_failure: Option<usize>,
+}
+
+/// A prompt of a [`JobGroup`].
+#[derive(Debug)]
+struct Job {
+    handle: JobHandle,
+    collector: OutputCollector,
+    progress: f64,
+    /// Whether the progress is weighted by the nodes reported via
+    /// `progress_state` events, which supersede `progress` events.
+    node_progress: bool,
+    outcome: Option<Outcome>,
+}
+
+impl Job {
+    /// Advances the progress of the prompt, which never decreases, e.g. when
+    /// the next sampler of a workflow starts.
+    fn advance(&mut self, progress: f64) {
+        self.progress = self.progress.max(progress);
+    }
+}
+
+/// How the execution of a prompt of a [`JobGroup`] terminated.
+#[derive(Debug)]
+enum Outcome {
+    /// The execution succeeded, with the history if already retrieved.
+    Succeeded(Option<History>),
+    Failed(Box<ClientError>),
+}
+
+/// The result of a prompt of a [`JobGroup`], see [`JobGroup::wait`].
+#[derive(Debug)]
+pub struct JobResult {
+    /// The handle of the prompt.
+    pub handle: JobHandle,
+    /// The outputs of the prompt, or the error it failed with.
+    pub result: ClientResult<WorkflowOutputs>,
+}
+
+impl JobResult {
+    /// Returns why the prompt was cancelled, e.g. because it was deleted from
+    /// the queue, the deadline elapsed or the connection was lost before it
+    /// terminated.
+    ///
+    /// # Returns
+    ///
+    /// The [`CancellationReason`], or `None` if the prompt wasn't cancelled.
+    pub fn cancellation_reason(&self) -> Option<&CancellationReason> {
+        match &self.result {
+            Err(ClientError::Cancelled(reason)) => Some(reason),
+            _ => None,
+        }
+    }
+}
+
+/// The aggregate progress of the prompts of a [`JobGroup`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GroupProgress {
+    /// The number of prompts in the group.
+    pub total: usize,
+    /// The number of prompts whose execution succeeded.
+    pub succeeded: usize,
+    /// The number of prompts whose execution failed or was interrupted.
+    pub failed: usize,
+    /// The overall progress, from `0.0` to `1.0`, which never decreases.
+    /// Terminated prompts count as complete. Executing ones count by the
+    /// share of their finished node steps if the server reports the
+    /// `progress_state` of their nodes, or else by the step progress of their
+    /// executing node.
+    pub fraction: f64,
+}
+
+impl JobGroup {
+    /// Creates a new, empty [`JobGroup`], which waits for all prompts even if
+    /// some of them fail.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets whether the group resolves on the first failing prompt, cancelling
+    /// the remaining ones.
+    ///
+    /// # Parameters
+    ///
+    /// - `fail_fast`: Whether to fail fast. Defaults to `false`.
+    ///
+    /// # Returns
+    ///
+    /// The updated [`JobGroup`].
+    pub fn fail_fast(mut self, fail_fast: bool) -> Self {
+        self.fail_fast = fail_fast;
+        self
+    }
+
+    /// Adds an already sent prompt to the group.
+    ///
+    /// # Parameters
+    ///
+    /// - `handle`: The [`JobHandle`] of the prompt.
+    pub fn add(&mut self, handle: JobHandle) {
+        self.jobs.push(Job {
+            collector: OutputCollector::new(handle.prompt_id()),
+            handle,
+            progress: 0.0,
+            node_progress: false,
+            outcome: None,
+        });
+    }
+
+    /// Sends a prompt and adds it to the group.
+    ///
+    /// # Parameters
+    ///
+    /// - `client`: The client to send the prompt with.
+    /// - `prompt`: The prompt to send.
+    ///
+    /// # Returns
+    ///
+    /// The [`JobHandle`] of the sent prompt, or an error.
+    pub async fn submit(
+        &mut self, client: &ComfyUIClient, prompt: impl Into<Prompt<'_>>,
+    ) -> ClientResult<JobHandle> {
+        let handle = JobHandle::from(client.post_prompt(prompt).await?);
+        self.add(handle.clone());
+        Ok(handle)
+    }
+
+    /// Returns the handles of the prompts in the group, in the order they were
+    /// added.
+    pub fn handles(&self) -> impl Iterator<Item = &JobHandle> {
+        self.jobs.iter().map(|job| &job.handle)
+    }
+
+    /// Returns the aggregate progress of the prompts in the group.
+    pub fn progress(&self) -> GroupProgress {
+        let count = |f: fn(&Outcome) -> bool| {
+            self.jobs
+                .iter()
+                .filter(|job| job.outcome.as_ref().is_some_and(f))
+                .count()
+        };
+        let fraction = if self.jobs.is_empty() {
+            1.0
+        } else {
+            self.jobs.iter().map(|job| job.progress).sum::<f64>() / self.jobs.len() as f64
+        };
+        GroupProgress {
+            total: self.jobs.len(),
+            succeeded: count(|outcome| matches!(outcome, Outcome::Succeeded(_))),
+            failed: count(|outcome| matches!(outcome, Outcome::Failed(_))),
+            fraction,
+        }
+    }
+
+    /// Checks whether the group has resolved, i.e. all prompts have
+    /// terminated, or one has failed when failing fast.
+    pub fn is_resolved(&self) -> bool {
+        (self.fail_fast && self.first_failure.is_some())
+            || self.jobs.iter().all(|job| job.outcome.is_some())
+    }
+
+    /// Updates the state of the group according to an event.
+    ///
+    /// # Parameters
+    ///
+    /// - `ev`: An [`Event`] received from the [`EventStream`].
+    ///
+    /// # Returns
+    ///
+    /// `true` if the group has resolved, see
+    /// [`is_resolved`](Self::is_resolved).
+    pub fn observe(&mut self, ev: &Event) -> bool {
+        let Event::Comfy(comfy_ev) = ev else {
+            return self.is_resolved();
+        };
+        let index = comfy_ev.prompt_id().and_then(|prompt_id| {
+            self.jobs
+                .iter()
+                .position(|job| job.handle.prompt_id == prompt_id)
+        });
+        if let Some(index) = index {
+            let job = &mut self.jobs[index];
+            job.collector.observe(ev);
+            if job.outcome.is_none() {
+                match comfy_ev {
+                    ComfyEvent::ProgressState { data } if !data.nodes.is_empty() => {
+                        job.node_progress = true;
+                        let steps = data
+                            .nodes
+                            .values()
+                            .map(|node| match node.state {
+                                NodeState::Finished | NodeState::Error => 1.0,
+                                _ if node.max > 0.0 => (node.value / node.max).clamp(0.0, 1.0),
+                                _ => 0.0,
+                            })
+                            .sum::<f64>();
+                        job.advance(steps / data.nodes.len() as f64);
+                    }
+                    ComfyEvent::Progress { data } if data.max > 0 && !job.node_progress => {
+                        job.advance(data.value.min(data.max) as f64 / data.max as f64);
+                    }
+                    ComfyEvent::ExecutionSuccess { .. } => {
+                        self.finish(index, Outcome::Succeeded(None));
+                    }
+                    ComfyEvent::ExecutionError { data } => {
+                        let err = ClientError::Execution(Box::new(data.clone()));
+                        self.finish(index, Outcome::Failed(Box::new(err)));
+                    }
+                    ComfyEvent::ExecutionInterrupted { data } => {
+                        let err =
+                            ClientError::Cancelled(CancellationReason::Interrupted(data.clone()));
+                        self.finish(index, Outcome::Failed(Box::new(err)));
+                    }
+                    _ => {}
+                }
+            }
+        }
+        self.is_resolved()
+    }
+
+    /// Records how the execution of a prompt terminated.
+    fn finish(&mut self, index: usize, outcome: Outcome) {
+        if matches!(outcome, Outcome::Failed(_)) {
+            self.first_failure.get_or_insert(index);
+        }
+        let job = &mut self.jobs[index];
+        job.progress = 1.0;
+        job.outcome = Some(outcome);
+    }
+
+    /// Gives up on the prompts which haven't terminated yet, without counting
+    /// them as the first failure when failing fast.
+    fn abandon(&mut self, reason: impl Fn() -> CancellationReason) {
+        for job in self.jobs.iter_mut().filter(|job| job.outcome.is_none()) {
+            job.outcome = Some(Outcome::Failed(Box::new(ClientError::Cancelled(reason()))));
+        }
+    }
+
+    /// Cancels the prompts which haven't terminated by the deadline of
+    /// [`wait`](Self::wait). The timeout is reported even if the cancellation
+    /// fails, so failures are only logged.
+    async fn cancel_timed_out(&self, client: &ComfyUIClient) {
+        let cancellations = self
+            .jobs
+            .iter()
+            .filter(|job| job.outcome.is_none())
+            .map(|job| async move {
+                if let Err(err) = client.cancel_prompt(&job.handle.prompt_id).await {
+                    warn!(err:%, prompt_id:% = job.handle.prompt_id; "cancel timed out prompt failed");
+                }
+            });
+        join_all(cancellations).await;
+    }
+
+    /// Resolves the prompts which have terminated while their events were
+    /// missed, e.g. because they were deleted from the queue, see
+    /// [`ComfyUIClient::check_prompt`].
+    async fn reconcile(&mut self, client: &ComfyUIClient) -> ClientResult<()> {
+        let queue = client.get_queue().await?;
+        for index in 0..self.jobs.len() {
+            let job = &self.jobs[index];
+            if job.outcome.is_some() {
+                continue;
+            }
+            match client.check_prompt(&queue, &job.handle.prompt_id).await? {
+                PromptCheck::Queued => {}
+                PromptCheck::Finished(history) => {
+                    self.finish(index, Outcome::Succeeded(Some(history)));
+                }
+                PromptCheck::Failed(err) => self.finish(index, Outcome::Failed(Box::new(err))),
+            }
+        }
+        Ok(())
+    }
+
+    /// Cancels the prompts of the group which haven't terminated yet, see
+    /// [`ComfyUIClient::cancel_prompt`].
+    ///
+    /// # Parameters
+    ///
+    /// - `client`: The client the prompts were sent with.
+    ///
+    /// # Returns
+    ///
+    /// `()` once the prompts are no longer in the queue, or an error.
+    pub async fn cancel(&self, client: &ComfyUIClient) -> ClientResult<()> {
+        try_join_all(
+            self.jobs
+                .iter()
+                .filter(|job| job.outcome.is_none())
+                .map(|job| client.cancel_prompt(&job.handle.prompt_id)),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Consumes events from the stream until the group resolves, and returns
+    /// the outputs of its prompts.
+    ///
+    /// Like [`ComfyUIClient::execute`], the queue and the history are checked
+    /// when the queue changes and after a reconnection, so that prompts
+    /// deleted from the queue resolve too. Errors of the stream other than a
+    /// lost connection, e.g. events of other prompts which can't be decoded,
+    /// are logged and skipped.
+    ///
+    /// # Parameters
+    ///
+    /// - `client`: The client the prompts were sent with.
+    /// - `stream`: The [`EventStream`] of the same client.
+    /// - `deadline`: The maximum duration to wait for the group, if any. Once
+    ///   it elapses, the remaining prompts are cancelled via
+    ///   [`ComfyUIClient::cancel_prompt`], and a failing cancellation is
+    ///   logged.
+    ///
+    /// # Returns
+    ///
+    /// The [`JobResult`] of each prompt, in the order they were added. A
+    /// failure to retrieve the history of a prompt is returned as its result.
+    /// Prompts which haven't terminated when the deadline elapses or the
+    /// websocket connection is lost result in a [`ClientError::Cancelled`]
+    /// with [`CancellationReason::Timeout`] or
+    /// [`CancellationReason::ConnectionLost`], while the results of the
+    /// terminated ones are kept. When failing fast, the error of the first
+    /// failing prompt is returned instead, once the remaining prompts have
+    /// been cancelled.
+    pub async fn wait(
+        mut self, client: &ComfyUIClient, stream: &mut EventStream, deadline: Option<Duration>,
+    ) -> ClientResult<Vec<JobResult>> {
+        let expired = async {
+            match deadline {
+                Some(deadline) => {
+                    sleep(deadline).await;
+                    deadline
+                }
+                None => pending().await,
+            }
+        };
+        tokio::pin!(expired);
+        let mut queue_remaining = None;
+        while !self.is_resolved() {
+            let ev = tokio::select! {
+                ev = stream.next() => ev,
+                deadline = &mut expired => {
+                    self.cancel_timed_out(client).await;
+                    self.abandon(|| CancellationReason::Timeout(deadline));
+                    break;
+                }
+            };
+            let reconcile = match ev {
+                Some(Ok(ev)) => {
+                    self.observe(&ev);
+                    match ev {
+                        Event::Comfy(ComfyEvent::Status { data, .. }) => {
+                            let remaining = data.status.exec_info.queue_remaining;
+                            queue_remaining.replace(remaining) != Some(remaining)
+                        }
+                        Event::Connection(ConnectionEvent::WSReconnectSuccess) => true,
+                        _ => false,
+                    }
+                }
+                Some(Err(ClientError::Tungstenite(_))) | None => {
+                    self.abandon(|| CancellationReason::ConnectionLost);
+                    break;
+                }
+                Some(Err(err)) => {
+                    warn!(err:%; "skipped event stream error");
+                    false
+                }
+            };
+            if reconcile && !self.is_resolved() {
+                if let Err(err) = self.reconcile(client).await {
+                    warn!(err:%; "check queue failed");
+                    queue_remaining = None;
+                }
+            }
+        }
+
+        if let Some(index) = self.first_failure.filter(|_| self.fail_fast) {
+            self.cancel(client).await?;
+            if let Some(Outcome::Failed(err)) = self.jobs.swap_remove(index).outcome {
+                return Err(*err);
+            }
+        }
+
+        let mut results = Vec::with_capacity(self.jobs.len());
+        for job in self.jobs {
+            let prompt_id = job.handle.prompt_id.clone();
+            let result = match job.outcome {
+                Some(Outcome::Failed(err)) => Err(*err),
+                Some(Outcome::Succeeded(Some(history))) => Ok(job.collector.finish(history)),
+                _ => match client.get_history(&prompt_id).await {
+                    Ok(Some(history)) => Ok(job.collector.finish(history)),
+                    Ok(None) => Err(ClientError::HistoryNotFound(prompt_id)),
+                    Err(err) => Err(err),
+                },
+            };
+            results.push(JobResult {
+                handle: job.handle,
+                result,
+            });
+        }
+        Ok(results)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ClientBuilder,
+        errors::EventDecodeError,
+        tests::{serve, serve_json},
+    };
+    use reqwest::StatusCode;
+    use serde_json::{Value, json};
+
+    fn event(value: Value) -> Event {
+        Event::Comfy(serde_json::from_value(value).unwrap())
+    }
+
+    fn error(prompt_id: &str) -> Event {
+        event(json!({
+            "type": "execution_error",
+            "data": {
+                "prompt_id": prompt_id,
+                "node_id": "3",
+                "node_type": "KSampler",
+                "executed": [],
+                "exception_message": "out of memory",
+                "exception_type": "RuntimeError",
+                "traceback": [],
+                "current_inputs": {},
+                "current_outputs": {}
+            }
+        }))
+    }
+
+    #[test]
+    fn test_group_progress() {
+        let mut group = JobGroup::new();
+        group.add(JobHandle::new("a", 1));
+        group.add(JobHandle::new("b", 2));
+        assert_eq!(group.progress().fraction, 0.0);
+
+        assert!(!group.observe(&event(json!({
+            "type": "progress",
+            "data": {"value": 5, "max": 10, "prompt_id": "a", "node": "3"}
+        }))));
+        assert!(!group.observe(&event(json!({
+            "type": "progress",
+            "data": {"value": 5, "max": 10, "prompt_id": "other", "node": "3"}
+        }))));
+        assert_eq!(group.progress().fraction, 0.25);
+
+        assert!(!group.observe(&event(json!({
+            "type": "execution_success",
+            "data": {"prompt_id": "b", "timestamp": 1}
+        }))));
+        assert!(group.observe(&error("a")));
+        assert_eq!(
+            group.progress(),
+            GroupProgress {
+                total: 2,
+                succeeded: 1,
+                failed: 1,
+                fraction: 1.0,
+            }
+        );
+    }
+
+    #[test]
+    fn test_group_progress_multiple_samplers() {
+        let progress_state = |prompt_id: &str, first: Value, second: Value| {
+            event(json!({
+                "type": "progress_state",
+                "data": {
+                    "prompt_id": prompt_id,
+                    "nodes": {
+                        "3": {"node_id": "3", "max": 10, "value": first[0], "state": first[1]},
+                        "6": {"node_id": "6", "max": 10, "value": second[0], "state": second[1]}
+                    }
+                }
+            }))
+        };
+        let progress = |prompt_id: &str, node: &str, value: usize| {
+            event(json!({
+                "type": "progress",
+                "data": {"value": value, "max": 10, "prompt_id": prompt_id, "node": node}
+            }))
+        };
+
+        // Weighted by the nodes of the prompt.
+        let mut group = JobGroup::new();
+        group.add(JobHandle::new("a", 1));
+        group.observe(&progress_state(
+            "a",
+            json!([5, "running"]),
+            json!([0, "pending"]),
+        ));
+        assert_eq!(group.progress().fraction, 0.25);
+        group.observe(&progress("a", "6", 1));
+        assert_eq!(group.progress().fraction, 0.25);
+        group.observe(&progress_state(
+            "a",
+            json!([10, "finished"]),
+            json!([5, "running"]),
+        ));
+        assert_eq!(group.progress().fraction, 0.75);
+
+        // Without the progress of the nodes, the step progress of the second
+        // sampler doesn't set the progress back.
+        let mut group = JobGroup::new();
+        group.add(JobHandle::new("b", 2));
+        group.observe(&progress("b", "3", 8));
+        assert_eq!(group.progress().fraction, 0.8);
+        group.observe(&progress("b", "6", 2));
+        assert_eq!(group.progress().fraction, 0.8);
+    }
+
+    #[test]
+    fn test_group_fail_fast() {
+        let mut group = JobGroup::new().fail_fast(true);
+        group.add(JobHandle::new("a", 1));
+        group.add(JobHandle::new("b", 2));
+        assert!(group.observe(&error("b")));
+        assert_eq!(group.progress().failed, 1);
+
+        let mut group = JobGroup::new();
+        group.add(JobHandle::new("a", 1));
+        group.add(JobHandle::new("b", 2));
+        assert!(!group.observe(&error("b")));
+    }
+
+    #[tokio::test]
+    async fn test_group_wait() {
+        let (base_url, mut requests) = serve(vec![
+            (
+                StatusCode::OK,
+                json!({
+                    "a": {"outputs": {"9": {"images": [{"filename": "a.png", "subfolder": "", "type": "output"}]}}}
+                }),
+            ),
+            (StatusCode::INTERNAL_SERVER_ERROR, json!({})),
+        ])
+        .await;
+        let client = ClientBuilder::new(base_url.as_str())
+            .build_only_http()
+            .await
+            .unwrap();
+
+        let (tx, rx) = tokio::sync::mpsc::channel(8);
+        let mut stream = EventStream::from_receiver(rx);
+        tx.send(Ok(error("b"))).await.unwrap();
+        for prompt_id in ["a", "c"] {
+            tx.send(Ok(event(json!({
+                "type": "execution_success",
+                "data": {"prompt_id": prompt_id, "timestamp": 1}
+            }))))
+            .await
+            .unwrap();
+        }
+
+        let mut group = JobGroup::new();
+        group.add(JobHandle::new("a", 1));
+        group.add(JobHandle::new("b", 2));
+        group.add(JobHandle::new("c", 3));
+        let results = group.wait(&client, &mut stream, None).await.unwrap();
+        assert_eq!(results[0].handle.prompt_id(), "a");
+        assert_eq!(
+            results[0]
+                .result
+                .as_ref()
+                .unwrap()
+                .images()
+                .next()
+                .unwrap()
+                .filename,
+            "a.png"
+        );
+        assert!(matches!(results[1].result, Err(ClientError::Execution(_))));
+        assert!(matches!(results[2].result, Err(ClientError::Api(_))));
+        for prompt_id in ["a", "c"] {
+            assert!(
+                requests
+                    .recv()
+                    .await
+                    .unwrap()
+                    .starts_with(&format!("GET /history/{prompt_id} "))
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_group_wait_fail_fast() {
+        let (base_url, mut requests) =
+            serve_json(vec![json!({"queue_running": [], "queue_pending": []})]).await;
+        let client = ClientBuilder::new(base_url.as_str())
+            .build_only_http()
+            .await
+            .unwrap();
+
+        let (tx, rx) = tokio::sync::mpsc::channel(8);
+        let mut stream = EventStream::from_receiver(rx);
+        tx.send(Ok(error("b"))).await.unwrap();
+
+        let mut group = JobGroup::new().fail_fast(true);
+        group.add(JobHandle::new("a", 1));
+        group.add(JobHandle::new("b", 2));
+        let err = group.wait(&client, &mut stream, None).await.unwrap_err();
+        assert!(matches!(err, ClientError::Execution(data) if data.prompt_id == "b"));
+        // The remaining prompt has been cancelled.
+        assert!(requests.recv().await.unwrap().starts_with("GET /queue "));
+    }
+
+    #[tokio::test]
+    async fn test_group_wait_reconcile() {
+        let (base_url, mut requests) = serve_json(vec![
+            json!({"queue_running": [[2, "b", {}, {}, []]], "queue_pending": []}),
+            json!({}),
+            json!({"b": {"outputs": {}}}),
+        ])
+        .await;
+        let client = ClientBuilder::new(base_url.as_str())
+            .build_only_http()
+            .await
+            .unwrap();
+
+        let (tx, rx) = tokio::sync::mpsc::channel(8);
+        let mut stream = EventStream::from_receiver(rx);
+        tx.send(Ok(event(json!({
+            "type": "status",
+            "data": {"status": {"exec_info": {"queue_remaining": 1}}}
+        }))))
+        .await
+        .unwrap();
+        // A strict decoding error of an unrelated event doesn't abort the
+        // group.
+        tx.send(Err(EventDecodeError {
+            type_field: Some("custom".to_string()),
+            serde_error: serde_json::from_str::<Value>("").unwrap_err(),
+            raw: Value::Null,
+        }
+        .into()))
+            .await
+            .unwrap();
+        tx.send(Ok(event(json!({
+            "type": "execution_success",
+            "data": {"prompt_id": "b", "timestamp": 1}
+        }))))
+        .await
+        .unwrap();
+
+        let mut group = JobGroup::new();
+        group.add(JobHandle::new("a", 1));
+        group.add(JobHandle::new("b", 2));
+        let results = group.wait(&client, &mut stream, None).await.unwrap();
+        assert!(matches!(
+            results[0].cancellation_reason(),
+            Some(CancellationReason::QueueDeleted)
+        ));
+        assert!(results[1].result.is_ok());
+        for request in ["GET /queue ", "GET /history/a ", "GET /history/b "] {
+            assert!(requests.recv().await.unwrap().starts_with(request));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_group_wait_connection_lost() {
+        let (base_url, _requests) = serve_json(vec![json!({"a": {"outputs": {}}})]).await;
+        let client = ClientBuilder::new(base_url.as_str())
+            .build_only_http()
+            .await
+            .unwrap();
+
+        let (tx, rx) = tokio::sync::mpsc::channel(8);
+        let mut stream = EventStream::from_receiver(rx);
+        tx.send(Ok(event(json!({
+            "type": "execution_success",
+            "data": {"prompt_id": "a", "timestamp": 1}
+        }))))
+        .await
+        .unwrap();
+        drop(tx);
+
+        let mut group = JobGroup::new();
+        group.add(JobHandle::new("a", 1));
+        group.add(JobHandle::new("b", 2));
+        let results = group.wait(&client, &mut stream, None).await.unwrap();
+        // The outcome of the terminated prompt is kept.
+        assert!(results[0].result.is_ok());
+        assert!(matches!(
+            results[1].cancellation_reason(),
+            Some(CancellationReason::ConnectionLost)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_group_wait_deadline() {
+        let (base_url, mut requests) =
+            serve_json(vec![json!({"queue_running": [], "queue_pending": []})]).await;
+        let client = ClientBuilder::new(base_url.as_str())
+            .build_only_http()
+            .await
+            .unwrap();
+
+        let (_tx, rx) = tokio::sync::mpsc::channel(8);
+        let mut stream = EventStream::from_receiver(rx);
+        let mut group = JobGroup::new();
+        group.add(JobHandle::new("a", 1));
+        let deadline = Duration::from_millis(50);
+        let results = group
+            .wait(&client, &mut stream, Some(deadline))
+            .await
+            .unwrap();
+        assert!(matches!(
+            results[0].cancellation_reason(),
+            Some(CancellationReason::Timeout(elapsed)) if *elapsed == deadline
+        ));
+        // The timed out prompt has been cancelled.
+        assert!(requests.recv().await.unwrap().starts_with("GET /queue "));
+    }
+}
@@ -1,7 +1,23 @@
-use crate::ClientError;
-use serde::{Deserialize, Serialize};
-use serde_json::Value;
-use std::{collections::HashMap, fmt::Debug};
+use crate::{
+    ClientError, ClientResult,
+    errors::{CancellationReason, ErrorKind, EventDecodeError, MissingDependency, ValidationError},
+};
+use bytes::Bytes;
+use log::warn;
+use serde::{
+    Deserialize, Deserializer, Serialize,
+    de::{DeserializeOwned, MapAccess, Visitor},
+};
+use serde_json::{Value, value::RawValue};
+use std::{
+    borrow::Cow,
+    cmp::Ordering,
+    collections::HashMap,
+    fmt::{self, Debug},
+    marker::PhantomData,
+    path::Path,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 use tokio_tungstenite::tungstenite;
 
 /// Contains information about a prompt, including its execution details.
@@ -18,6 +34,718 @@ pub struct ExecInfo {
     pub queue_remaining: usize,
 }
 
+/// Contains the running and pending prompts of the queue.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct Queue {
+    /// The prompts currently executing.
+    pub queue_running: Vec<QueueItem>,
+    /// The prompts waiting for execution, in no particular order.
+    pub queue_pending: Vec<QueueItem>,
+}
+
+impl Queue {
+    /// Checks whether the prompt is currently executing.
+    pub fn is_running(&self, prompt_id: &str) -> bool {
+        self.queue_running
+            .iter()
+            .any(|item| item.prompt_id == prompt_id)
+    }
+
+    /// Checks whether the prompt is waiting for execution.
+    pub fn is_pending(&self, prompt_id: &str) -> bool {
+        self.queue_pending
+            .iter()
+            .any(|item| item.prompt_id == prompt_id)
+    }
+
+    /// Returns the position of a prompt in the queue, which is the number of
+    /// prompts executed before it.
+    ///
+    /// A running prompt has position `0`, while a pending prompt is preceded
+    /// by the running prompts and the pending prompts with a lower number.
+    ///
+    /// # Parameters
+    ///
+    /// - `prompt_id`: The ID of the prompt.
+    ///
+    /// # Returns
+    ///
+    /// The position, or `None` if the prompt is neither running nor pending.
+    pub fn position(&self, prompt_id: &str) -> Option<usize> {
+        if self.is_running(prompt_id) {
+            return Some(0);
+        }
+        let item = self
+            .queue_pending
+            .iter()
+            .find(|item| item.prompt_id == prompt_id)?;
+        let ahead = self
+            .queue_pending
+            .iter()
+            .filter(|other| other.number < item.number)
+            .count();
+        Some(self.queue_running.len() + ahead)
+    }
+}
+
+/// A prompt in the queue.
+///
+/// ComfyUI represents queue items as arrays, which are converted from and to
+/// this structure.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[serde(try_from = "Vec<Value>", into = "Vec<Value>")]
+pub struct QueueItem {
+    /// The number of the prompt, which determines the execution order.
+    pub number: i64,
+    /// Unique identifier for the prompt.
+    pub prompt_id: String,
+    /// The prompt data.
+    pub prompt: Value,
+    /// Extra data sent along with the prompt.
+    pub extra_data: Value,
+    /// The identifiers of the output nodes to execute.
+    pub outputs_to_execute: Vec<String>,
+}
+
+impl TryFrom<Vec<Value>> for QueueItem {
+    type Error = String;
+
+    fn try_from(value: Vec<Value>) -> Result<Self, Self::Error> {
+        let mut fields = value.into_iter();
+        let mut next = |name: &str| {
+            fields
+                .next()
+                .ok_or_else(|| format!("missing queue item field `{name}`"))
+        };
+        let number = next("number")?;
+        let prompt_id = next("prompt_id")?;
+        Ok(Self {
+            number: lenient::i64(number).map_err(|_| "invalid queue item field `number`")?,
+            prompt_id: prompt_id
+                .as_str()
+                .ok_or("invalid queue item field `prompt_id`")?
+                .to_string(),
+            prompt: next("prompt")?,
+            extra_data: next("extra_data").unwrap_or_default(),
+            outputs_to_execute: serde_json::from_value(
+                next("outputs_to_execute").unwrap_or_default(),
+            )
+            .unwrap_or_default(),
+        })
+    }
+}
+
+impl From<QueueItem> for Vec<Value> {
+    fn from(item: QueueItem) -> Self {
+        vec![
+            item.number.into(),
+            item.prompt_id.into(),
+            item.prompt,
+            item.extra_data,
+            item.outputs_to_execute.into(),
+        ]
+    }
+}
+
+/// The outcome of
+/// [`ComfyUIClient::cancel_prompt`](crate::ComfyUIClient::cancel_prompt).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CancelOutcome {
+    /// The prompt was removed from the pending queue before it started.
+    Dequeued,
+    /// The prompt was executing and has been interrupted.
+    Interrupted,
+    /// The prompt was neither pending nor executing, e.g. because it has
+    /// already finished.
+    NotFound,
+    /// The prompt was still in the queue when the wait for its cancellation
+    /// ran out, e.g. because the executing node ignores interruptions.
+    Unconfirmed,
+}
+
+/// Contains statistics about the server system and its devices.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct SystemStats {
+    /// Information about the system running the server.
+    pub system: SystemInfo,
+    /// Statistics of the compute devices available to the server.
+    pub devices: Vec<DeviceStats>,
+}
+
+/// Contains information about the system running the server.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct SystemInfo {
+    /// The operating system, e.g. `posix` or `nt`.
+    pub os: String,
+    /// The total RAM in bytes.
+    pub ram_total: u64,
+    /// The free RAM in bytes.
+    pub ram_free: u64,
+    /// The version of ComfyUI, if reported.
+    pub comfyui_version: Option<String>,
+    /// The version of Python.
+    pub python_version: String,
+    /// The version of PyTorch, if reported.
+    pub pytorch_version: Option<String>,
+    /// Whether ComfyUI runs with an embedded Python, if reported.
+    pub embedded_python: Option<bool>,
+    /// The command line arguments the server was started with.
+    #[serde(default)]
+    pub argv: Vec<String>,
+}
+
+/// Contains statistics of a compute device.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct DeviceStats {
+    /// The name of the device, e.g. `cuda:0 NVIDIA GeForce RTX 4090`.
+    pub name: String,
+    /// The type of the device, e.g. `cuda` or `cpu`.
+    pub r#type: String,
+    /// The index of the device, if any.
+    pub index: Option<u32>,
+    /// The total VRAM in bytes.
+    pub vram_total: u64,
+    /// The free VRAM in bytes.
+    pub vram_free: u64,
+    /// The VRAM reserved by PyTorch in bytes.
+    pub torch_vram_total: u64,
+    /// The free VRAM within the memory reserved by PyTorch in bytes.
+    pub torch_vram_free: u64,
+}
+
+/// Contains the feature flags reported by the server.
+///
+/// Servers that predate the `features` endpoint don't report any flags, in
+/// which case all fields have their default values.
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+pub struct Features {
+    /// Whether preview images sent over the websocket carry metadata such as
+    /// the node and prompt they belong to.
+    #[serde(default)]
+    pub supports_preview_metadata: bool,
+    /// The maximum size of an upload in bytes, if reported.
+    pub max_upload_size: Option<u64>,
+    /// Additional flags that don't fit into predefined fields, e.g. those
+    /// reported by extensions.
+    #[serde(flatten)]
+    pub others: HashMap<String, Value>,
+}
+
+/// Contains the users known to the server.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct Users {
+    /// Where user data is stored, e.g. `server`.
+    pub storage: String,
+    /// The names of the users keyed by user ID, only reported by servers
+    /// started with `--multi-user`.
+    #[serde(default)]
+    pub users: HashMap<String, String>,
+    /// Whether the data of the default user has been migrated to the server,
+    /// only reported by single-user servers.
+    #[serde(default)]
+    pub migrated: bool,
+}
+
+/// Contains the recent log entries of the server, along with the size of its
+/// terminal.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct RawLogs {
+    /// The log entries, oldest first.
+    pub entries: Vec<LogEntry>,
+    /// The size of the terminal of the server, used to lay out the entries.
+    pub size: TerminalSize,
+}
+
+/// A log entry of the server.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub struct LogEntry {
+    /// The time the entry was logged, e.g. `2025-03-01T12:00:00.000000`.
+    #[serde(rename = "t")]
+    pub timestamp: String,
+    /// The logged message, which may span multiple lines such as a traceback.
+    #[serde(rename = "m")]
+    pub message: String,
+}
+
+/// The size of a terminal.
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub struct TerminalSize {
+    /// The number of columns, if known.
+    pub cols: Option<u32>,
+    /// The number of rows, if known.
+    pub rows: Option<u32>,
+}
+
+/// The schemas of all node classes available on the server, keyed by class
+/// type.
+///
+/// Retrieved via
+/// [`ComfyUIClient::get_object_info`](crate::ComfyUIClient::get_object_info).
+pub type ObjectInfo = HashMap<String, NodeInfo>;
+
+/// The schema of a node class, describing its inputs and outputs.
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+pub struct NodeInfo {
+    /// The inputs of the node.
+    #[serde(default)]
+    pub input: NodeInputs,
+    /// The order of the inputs per group (`required`, `optional`), which is
+    /// also the order of their widgets in the UI.
+    #[serde(default)]
+    pub input_order: HashMap<String, Vec<String>>,
+    /// The types of the outputs, e.g. `LATENT` or `IMAGE`.
+    #[serde(default, deserialize_with = "lenient::strings")]
+    pub output: Vec<String>,
+    /// Whether each output produces a list.
+    #[serde(default)]
+    pub output_is_list: Vec<bool>,
+    /// The names of the outputs.
+    #[serde(default, deserialize_with = "lenient::strings")]
+    pub output_name: Vec<String>,
+    /// The class type of the node.
+    #[serde(default)]
+    pub name: String,
+    /// The name of the node displayed in the UI.
+    #[serde(default)]
+    pub display_name: String,
+    /// The description of the node.
+    #[serde(default)]
+    pub description: String,
+    /// The Python module defining the node.
+    #[serde(default)]
+    pub python_module: String,
+    /// The category of the node, e.g. `sampling`.
+    #[serde(default)]
+    pub category: String,
+    /// Whether the node is an output node, such as `SaveImage`.
+    #[serde(default)]
+    pub output_node: bool,
+    /// Whether the node is deprecated.
+    #[serde(default)]
+    pub deprecated: bool,
+    /// Whether the node is experimental.
+    #[serde(default)]
+    pub experimental: bool,
+}
+
+/// The inputs of a node class, grouped by whether they are required.
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+pub struct NodeInputs {
+    /// The inputs that must be provided.
+    #[serde(default)]
+    pub required: HashMap<String, InputSpec>,
+    /// The inputs that may be omitted.
+    #[serde(default)]
+    pub optional: HashMap<String, InputSpec>,
+    /// The inputs filled in by the server, such as `prompt` or
+    /// `unique_id`.
+    #[serde(default)]
+    pub hidden: HashMap<String, Value>,
+}
+
+impl NodeInputs {
+    /// Returns the specification of a required or optional input.
+    pub fn get(&self, name: &str) -> Option<&InputSpec> {
+        self.required.get(name).or_else(|| self.optional.get(name))
+    }
+}
+
+/// The specification of a node input, consisting of its type and options.
+///
+/// ComfyUI represents input specifications as arrays like
+/// `["INT", {"default": 20, "min": 1}]`, which are converted from and to this
+/// structure.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[serde(from = "Value", into = "Value")]
+pub struct InputSpec {
+    /// The type of the input.
+    pub r#type: InputType,
+    /// The options of the input.
+    pub options: InputOptions,
+}
+
+impl InputSpec {
+    /// Returns the allowed values if the input is a combo (dropdown) input.
+    ///
+    /// Supports both the legacy `[[values...]]` and the `["COMBO", {"options":
+    /// [values...]}]` representation.
+    pub fn combo_values(&self) -> Option<&[Value]> {
+        match &self.r#type {
+            InputType::Combo(values) => Some(values),
+            InputType::Named(name) if name == "COMBO" => self.options.options.as_deref(),
+            InputType::Named(_) => None,
+        }
+    }
+}
+
+impl From<Value> for InputSpec {
+    fn from(value: Value) -> Self {
+        let mut items = match value {
+            Value::Array(items) => items.into_iter(),
+            value => vec![value].into_iter(),
+        };
+        let r#type = match items.next() {
+            Some(Value::Array(values)) => InputType::Combo(values),
+            Some(Value::String(name)) => InputType::Named(name),
+            Some(value) => InputType::Named(value.to_string()),
+            None => InputType::Named(String::new()),
+        };
+        let options = match items.next() {
+            Some(Value::Object(map)) => serde_json::from_value(Value::Object(map.clone()))
+                .unwrap_or_else(|_| InputOptions {
+                    others: map.into_iter().collect(),
+                    ..Default::default()
+                }),
+            _ => InputOptions::default(),
+        };
+        Self { r#type, options }
+    }
+}
+
+impl From<InputSpec> for Value {
+    fn from(spec: InputSpec) -> Self {
+        let r#type = match spec.r#type {
+            InputType::Named(name) => Value::String(name),
+            InputType::Combo(values) => Value::Array(values),
+        };
+        let options = serde_json::to_value(spec.options).unwrap_or_default();
+        Value::Array(vec![r#type, options])
+    }
+}
+
+/// The type of a node input.
+#[derive(Clone, Debug, PartialEq)]
+pub enum InputType {
+    /// A named type, e.g. `INT`, `STRING`, `MODEL` or `COMBO`.
+    Named(String),
+    /// A combo (dropdown) input with its allowed values, in the legacy
+    /// representation.
+    Combo(Vec<Value>),
+}
+
+/// The options of a node input.
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+pub struct InputOptions {
+    /// The default value of the input.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<Value>,
+    /// The minimum value of a numeric input.
+    #[serde(
+        default,
+        deserialize_with = "lenient::opt_f64",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub min: Option<f64>,
+    /// The maximum value of a numeric input.
+    #[serde(
+        default,
+        deserialize_with = "lenient::opt_f64",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub max: Option<f64>,
+    /// The step of a numeric input.
+    #[serde(
+        default,
+        deserialize_with = "lenient::opt_f64",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub step: Option<f64>,
+    /// The allowed values of a `COMBO` input.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub options: Option<Vec<Value>>,
+    /// Additional options, e.g. `tooltip`, `multiline` or `forceInput`.
+    #[serde(flatten)]
+    pub others: HashMap<String, Value>,
+}
+
+/// Options for [`ComfyUIClient::free`](crate::ComfyUIClient::free).
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq, Eq)]
+pub struct FreeOptions {
+    /// Whether to unload all models from memory.
+    pub unload_models: bool,
+    /// Whether to free the memory (e.g. VRAM) held by cached data.
+    pub free_memory: bool,
+}
+
+/// Options for
+/// [`ComfyUIClient::upload_image_from_path`](crate::ComfyUIClient::upload_image_from_path).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UploadOptions {
+    /// The name of the file on the server. Defaults to the name of the local
+    /// file.
+    pub filename: Option<String>,
+    /// The subfolder on the server to upload into. Defaults to the root of
+    /// the directory.
+    pub subfolder: String,
+    /// The directory type to upload into, e.g. `input` or `temp`. Defaults
+    /// to `input`.
+    pub r#type: String,
+    /// Whether to overwrite an existing file. Defaults to `false`.
+    pub overwrite: bool,
+}
+
+impl Default for UploadOptions {
+    fn default() -> Self {
+        Self {
+            filename: None,
+            subfolder: String::new(),
+            r#type: "input".to_string(),
+            overwrite: false,
+        }
+    }
+}
+
+impl UploadOptions {
+    /// Returns the location on the server of the uploaded local file.
+    pub(crate) fn file_info(&self, path: &Path) -> FileInfo {
+        let filename = match &self.filename {
+            Some(filename) => filename.clone(),
+            None => path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+        };
+        FileInfo {
+            filename,
+            subfolder: self.subfolder.clone(),
+            r#type: self.r#type.clone(),
+        }
+    }
+}
+
+/// The progress of an upload, reported by
+/// [`ComfyUIClient::upload_image_with_progress`](crate::ComfyUIClient::upload_image_with_progress).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UploadProgress {
+    /// The number of bytes of the file sent so far.
+    pub sent: u64,
+    /// The size of the file in bytes, if known.
+    pub total: Option<u64>,
+}
+
+/// The policy for retrying idempotent HTTP requests, e.g.
+/// [`ComfyUIClient::get_history`](crate::ComfyUIClient::get_history), which
+/// failed transiently.
+///
+/// A request is retried if it failed with an [`ErrorKind::Transient`] error,
+/// e.g. if connecting or the request timed out, or if the server responds
+/// with `408 Request Timeout`, `429 Too Many Requests`, `502 Bad Gateway`,
+/// `503 Service Unavailable` or `504 Gateway Timeout`. Requests with a
+/// streaming body aren't retried.
+///
+/// See [`ClientBuilder::retry_policy`](crate::ClientBuilder::retry_policy).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// The maximum number of attempts, including the first one. Defaults to
+    /// 3.
+    pub max_attempts: u32,
+    /// The delay before the first retry, which doubles with each further
+    /// retry. Defaults to 500 milliseconds.
+    pub initial_backoff: Duration,
+    /// The maximum delay between two attempts. Defaults to 10 seconds.
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(10),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Returns the delay before the given retry, starting at 1.
+    pub(crate) fn backoff(&self, retry: u32) -> Duration {
+        let factor = 2u32.saturating_pow(retry.saturating_sub(1));
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+
+    /// Checks whether the outcome of an attempt is a transient failure, as
+    /// classified by [`ClientError::kind`](crate::errors::ClientError::kind).
+    pub(crate) fn is_transient(result: &reqwest::Result<reqwest::Response>) -> bool {
+        let kind = match result {
+            Ok(resp) if resp.status().is_success() => return false,
+            Ok(resp) => ErrorKind::of_status(resp.status()),
+            Err(err) => ErrorKind::of_reqwest(err),
+        };
+        kind == ErrorKind::Transient
+    }
+}
+
+/// The state of a client session, which can be persisted to resume the
+/// session after a restart.
+///
+/// Obtained via
+/// [`ComfyUIClient::session_state`](crate::ComfyUIClient::session_state) and
+/// restored via
+/// [`ClientBuilder::from_session_state`](crate::ClientBuilder::from_session_state).
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq, Eq)]
+pub struct SessionState {
+    /// The client ID, which the server adopts as session ID.
+    pub client_id: String,
+    /// The session ID last reported by the server in a `status` event, if
+    /// any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sid: Option<String>,
+    /// The IDs of the prompts which didn't terminate yet.
+    #[serde(default)]
+    pub prompt_ids: Vec<String>,
+}
+
+/// Options for
+/// [`ComfyUIClient::wait_for_completion_polling`](crate::ComfyUIClient::wait_for_completion_polling).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PollOptions {
+    /// The delay before the second poll, which doubles with each further
+    /// poll. Defaults to 500 milliseconds.
+    pub initial_interval: Duration,
+    /// The maximum delay between two polls. Defaults to 5 seconds.
+    pub max_interval: Duration,
+}
+
+impl Default for PollOptions {
+    fn default() -> Self {
+        Self {
+            initial_interval: Duration::from_millis(500),
+            max_interval: Duration::from_secs(5),
+        }
+    }
+}
+
+impl PollOptions {
+    /// Returns the delay after the given poll, starting at 1.
+    pub(crate) fn interval(&self, poll: u32) -> Duration {
+        let factor = 2u32.saturating_pow(poll.saturating_sub(1));
+        self.initial_interval
+            .saturating_mul(factor)
+            .min(self.max_interval)
+    }
+}
+
+/// Options for
+/// [`ComfyUIClient::post_prompt_with_options`](crate::ComfyUIClient::post_prompt_with_options).
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq, Eq)]
+pub struct PromptOptions {
+    /// Whether to insert the prompt at the front of the queue instead of
+    /// appending it.
+    pub front: bool,
+    /// The explicit number of the prompt, which determines its position in
+    /// the queue as prompts with lower numbers execute first. Takes
+    /// precedence over `front`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub number: Option<i64>,
+    /// Extra data stored along with the prompt, e.g. `extra_pnginfo` which
+    /// nodes such as `SaveImage` embed into the metadata of their outputs.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extra_data: Option<Value>,
+    /// The identifiers of the output nodes to execute, along with the nodes
+    /// they depend on. Executes all output nodes if `None`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub partial_execution_targets: Option<Vec<String>>,
+    /// The unique identifier of the prompt, e.g. a UUID generated by the
+    /// caller. This allows correlating events before the response arrives,
+    /// and checking whether the prompt was already queued before retrying.
+    /// Generated by the server if `None`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prompt_id: Option<String>,
+}
+
+impl PromptOptions {
+    /// Embeds the UI workflow into the metadata of the saved images, like the
+    /// web frontend does, so they can be dragged back into ComfyUI.
+    ///
+    /// Other entries of `extra_data` and `extra_pnginfo` are kept, unless they
+    /// aren't objects, in which case they are replaced.
+    ///
+    /// # Parameters
+    ///
+    /// - `workflow`: The workflow in UI format, as opposed to the prompt in API
+    ///   format.
+    ///
+    /// # Returns
+    ///
+    /// The updated [`PromptOptions`] instance.
+    pub fn with_workflow(mut self, workflow: Value) -> Self {
+        let extra_data = object_entry(
+            self.extra_data
+                .get_or_insert_with(|| Value::Object(Default::default())),
+        );
+        let extra_pnginfo = object_entry(
+            extra_data
+                .entry("extra_pnginfo")
+                .or_insert_with(|| Value::Object(Default::default())),
+        );
+        extra_pnginfo.insert("workflow".to_string(), workflow);
+        self
+    }
+}
+
+/// Returns the map of an object value, replacing the value by an empty object
+/// if it isn't one.
+fn object_entry(value: &mut Value) -> &mut serde_json::Map<String, Value> {
+    if !value.is_object() {
+        *value = Value::Object(Default::default());
+    }
+    match value {
+        Value::Object(map) => map,
+        _ => unreachable!("value was replaced by an object"),
+    }
+}
+
+/// How an [`EventSubscription`](crate::EventSubscription) handles events it
+/// missed because it lagged behind.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LagPolicy {
+    /// Logs a warning and continues with the oldest event still available.
+    #[default]
+    Skip,
+    /// Yields a [`ClientError::Lagged`] error and continues with the oldest
+    /// event still available.
+    Error,
+}
+
+/// Options for
+/// [`ComfyUIClient::get_view_with_options`](crate::ComfyUIClient::get_view_with_options).
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq, Eq)]
+pub struct ViewOptions {
+    /// The format to convert the image to, `webp` or `jpeg`, e.g. to fetch
+    /// small thumbnails. The original file is returned if `None`.
+    pub preview_format: Option<String>,
+    /// The quality of the converted image between 0 and 100, defaults to 90
+    /// on the server. Greater values are clamped to 100. Only used along with
+    /// `preview_format`.
+    pub quality: Option<u8>,
+    /// The channels of the image to return, one of `rgb`, `a` or `rgba`.
+    pub channel: Option<String>,
+}
+
+/// The maximum quality of [`ViewOptions::quality`].
+const MAX_VIEW_QUALITY: u8 = 100;
+
+impl ViewOptions {
+    /// Returns the query parameters of the `view` endpoint for the options.
+    pub(crate) fn query_pairs(&self) -> Vec<(&'static str, String)> {
+        let mut pairs = Vec::new();
+        if let Some(format) = &self.preview_format {
+            let preview = match self.quality {
+                Some(quality) => format!("{format};{}", quality.min(MAX_VIEW_QUALITY)),
+                None => format.clone(),
+            };
+            pairs.push(("preview", preview));
+        }
+        if let Some(channel) = &self.channel {
+            pairs.push(("channel", channel.clone()));
+        }
+        pairs
+    }
+}
+
 /// Represents file information including filename, subfolder, and file type.
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
 pub struct FileInfo {
@@ -35,17 +763,327 @@ pub struct FileInfo {
 pub struct PromptStatus {
     /// Unique identifier for the prompt.
     pub prompt_id: String,
-    /// A numeric identifier for the prompt.
-    pub number: usize,
+    /// The number of the prompt, which determines its position in the queue.
+    /// Negative for prompts inserted at the front of the queue.
+    #[serde(deserialize_with = "lenient::i64")]
+    pub number: i64,
     /// A mapping of node identifiers to error details in JSON format.
     pub node_errors: HashMap<String, Value>,
 }
 
+impl PromptStatus {
+    /// Returns the inputs that failed validation because the requested value,
+    /// such as a model or input file, is not available on the server.
+    pub fn missing_dependencies(&self) -> Vec<MissingDependency> {
+        MissingDependency::parse_node_errors(&self.node_errors)
+    }
+
+    /// Returns the validation errors of the nodes, ordered by node
+    /// identifier, like
+    /// [`ApiError::validation_errors`](crate::errors::ApiError::validation_errors).
+    pub fn validation_errors(&self) -> Vec<ValidationError> {
+        ValidationError::parse_node_errors(&self.node_errors)
+    }
+}
+
 /// Represents the history of outputs for a prompt.
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct History {
     /// A mapping of output identifiers to their corresponding images.
     pub outputs: HashMap<String, Images>,
+    /// The status of the execution, missing on older servers.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<HistoryStatus>,
+}
+
+impl History {
+    /// Returns the output of the node with the given identifier, if any.
+    pub fn output(&self, node_id: &str) -> Option<&Images> {
+        self.outputs.get(node_id)
+    }
+
+    /// Returns an iterator over the images produced by all nodes, in
+    /// ascending order of the node identifiers.
+    pub fn images(&self) -> impl Iterator<Item = &FileInfo> {
+        self.sorted_outputs().flat_map(Images::images)
+    }
+
+    /// Returns an iterator over the videos produced by all nodes.
+    pub fn videos(&self) -> impl Iterator<Item = &FileInfo> {
+        self.sorted_outputs().flat_map(Images::videos)
+    }
+
+    /// Returns an iterator over the audio files produced by all nodes.
+    pub fn audio(&self) -> impl Iterator<Item = &FileInfo> {
+        self.sorted_outputs().flat_map(Images::audio)
+    }
+
+    /// Returns an iterator over the texts produced by all nodes.
+    pub fn texts(&self) -> impl Iterator<Item = &str> {
+        self.sorted_outputs().flat_map(Images::texts)
+    }
+
+    /// Returns the first image produced by any node, if any.
+    ///
+    /// Nodes are visited in ascending order of their identifiers, so the
+    /// result is stable across calls.
+    pub fn first_image(&self) -> Option<&FileInfo> {
+        self.sorted_outputs().find_map(Images::first_image)
+    }
+
+    /// Returns an iterator over all files (images, videos and audio) produced
+    /// by all nodes.
+    pub fn all_files(&self) -> impl Iterator<Item = &FileInfo> {
+        self.sorted_outputs().flat_map(Images::all_files)
+    }
+
+    /// Returns the outputs of all nodes, in ascending order of the node
+    /// identifiers, so that the helpers above are stable across calls.
+    fn sorted_outputs(&self) -> impl Iterator<Item = &Images> {
+        let mut outputs = self.outputs.iter().collect::<Vec<_>>();
+        outputs.sort_by(|(a, _), (b, _)| compare_node_ids(a, b));
+        outputs.into_iter().map(|(_, output)| output)
+    }
+}
+
+impl AsRef<History> for History {
+    fn as_ref(&self) -> &History {
+        self
+    }
+}
+
+/// The status of an execution recorded in a [`History`].
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct HistoryStatus {
+    /// The status of the execution, either `success` or `error`.
+    pub status_str: String,
+    /// Whether the execution completed successfully.
+    #[serde(default)]
+    pub completed: bool,
+    /// The events of the execution, in the order they were emitted.
+    #[serde(default)]
+    pub messages: Vec<HistoryMessage>,
+}
+
+impl HistoryStatus {
+    /// Checks whether the execution succeeded.
+    pub fn is_success(&self) -> bool {
+        self.status_str == "success"
+    }
+
+    /// Returns the error of a failed or interrupted execution, as reported by
+    /// the corresponding event.
+    pub(crate) fn error(&self) -> Option<ClientError> {
+        self.messages
+            .iter()
+            .find_map(|message| match message.to_event() {
+                ComfyEvent::ExecutionError { data } => Some(ClientError::Execution(Box::new(data))),
+                ComfyEvent::ExecutionInterrupted { data } => Some(ClientError::Cancelled(
+                    CancellationReason::Interrupted(data),
+                )),
+                _ => None,
+            })
+    }
+}
+
+/// An event of an execution recorded in a [`HistoryStatus`], e.g.
+/// `execution_start` or `execution_error`.
+///
+/// ComfyUI represents messages as pairs of event type and data, which are
+/// converted from and to this structure.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[serde(from = "(String, Value)", into = "(String, Value)")]
+pub struct HistoryMessage {
+    /// The type of the event.
+    pub event_type: String,
+    /// The data of the event.
+    pub data: Value,
+}
+
+impl HistoryMessage {
+    /// Returns the server timestamp of the event in milliseconds, if any.
+    ///
+    /// See [`ClockSkew::to_local_time`] to convert it to the local clock.
+    pub fn timestamp(&self) -> Option<u64> {
+        self.data["timestamp"].as_u64()
+    }
+
+    /// Converts the message into the corresponding websocket event, falling
+    /// back to [`ComfyEvent::Unknown`] if it can't be decoded.
+    pub fn to_event(&self) -> ComfyEvent {
+        let value = serde_json::json!({"type": self.event_type, "data": self.data});
+        serde_json::from_value(value.clone()).unwrap_or(ComfyEvent::Unknown(value))
+    }
+}
+
+impl From<(String, Value)> for HistoryMessage {
+    fn from((event_type, data): (String, Value)) -> Self {
+        Self { event_type, data }
+    }
+}
+
+impl From<HistoryMessage> for (String, Value) {
+    fn from(message: HistoryMessage) -> Self {
+        (message.event_type, message.data)
+    }
+}
+
+/// The outputs of a prompt whose execution succeeded, returned by
+/// [`ComfyUIClient::execute`](crate::ComfyUIClient::execute).
+#[derive(Clone, Debug)]
+pub struct WorkflowOutputs {
+    /// The unique identifier of the executed prompt.
+    pub prompt_id: String,
+    /// The history of the prompt, containing the outputs per node.
+    pub history: History,
+}
+
+impl WorkflowOutputs {
+    /// Returns the output of the node with the given identifier, including
+    /// its raw non-file outputs, if any.
+    pub fn output(&self, node_id: &str) -> Option<&Images> {
+        self.history.output(node_id)
+    }
+
+    /// Returns the files (images and videos) produced by the node with the
+    /// given identifier.
+    pub fn files(&self, node_id: &str) -> impl Iterator<Item = &FileInfo> {
+        self.output(node_id).into_iter().flat_map(Images::all_files)
+    }
+
+    /// Returns an iterator over the images produced by all nodes.
+    pub fn images(&self) -> impl Iterator<Item = &FileInfo> {
+        self.history.images()
+    }
+
+    /// Returns an iterator over the videos produced by all nodes.
+    pub fn videos(&self) -> impl Iterator<Item = &FileInfo> {
+        self.history.videos()
+    }
+
+    /// Returns an iterator over the texts produced by all nodes.
+    pub fn texts(&self) -> impl Iterator<Item = &str> {
+        self.history.texts()
+    }
+
+    /// Returns an iterator over all files (images, videos and audio) produced
+    /// by all nodes.
+    pub fn all_files(&self) -> impl Iterator<Item = &FileInfo> {
+        self.history.all_files()
+    }
+}
+
+impl AsRef<History> for WorkflowOutputs {
+    fn as_ref(&self) -> &History {
+        &self.history
+    }
+}
+
+/// Collects the outputs of the nodes of a prompt from its `executed` events.
+///
+/// The history of a prompt may lack the outputs of some nodes, e.g. when it
+/// has been trimmed by the server. Merging the outputs received while the
+/// prompt executed via [`finish`](Self::finish) keeps them available.
+/// [`ComfyUIClient::execute`](crate::ComfyUIClient::execute) collects the
+/// outputs this way.
+#[derive(Clone, Debug)]
+pub struct OutputCollector {
+    prompt_id: String,
+    outputs: HashMap<String, ExecutedOutput>,
+}
+
+impl OutputCollector {
+    /// Creates a new [`OutputCollector`] for the given prompt.
+    pub fn new(prompt_id: &str) -> Self {
+        Self {
+            prompt_id: prompt_id.to_string(),
+            outputs: HashMap::new(),
+        }
+    }
+
+    /// Records the output of an `executed` event of the prompt. Events of
+    /// other prompts are ignored.
+    ///
+    /// # Parameters
+    ///
+    /// - `ev`: An [`Event`] received from the
+    ///   [`EventStream`](crate::EventStream).
+    ///
+    /// # Returns
+    ///
+    /// `true` if the event terminates the execution of the prompt, `false`
+    /// otherwise.
+    pub fn observe(&mut self, ev: &Event) -> bool {
+        let Event::Comfy(ev) = ev else {
+            return false;
+        };
+        if ev.prompt_id() != Some(&self.prompt_id) {
+            return false;
+        }
+        match ev {
+            ComfyEvent::Executed { data } => {
+                if let Some(output) = &data.output {
+                    self.outputs.insert(data.node.clone(), output.clone());
+                }
+                false
+            }
+            ComfyEvent::ExecutionSuccess { .. }
+            | ComfyEvent::ExecutionError { .. }
+            | ComfyEvent::ExecutionInterrupted { .. } => true,
+            _ => false,
+        }
+    }
+
+    /// Returns the outputs collected so far by node identifier.
+    pub fn outputs(&self) -> &HashMap<String, ExecutedOutput> {
+        &self.outputs
+    }
+
+    /// Merges the collected outputs into the history of the prompt.
+    ///
+    /// The outputs recorded in the history take precedence over the collected
+    /// ones.
+    ///
+    /// # Parameters
+    ///
+    /// - `history`: The history of the prompt.
+    ///
+    /// # Returns
+    ///
+    /// The [`WorkflowOutputs`] containing the outputs of all nodes.
+    pub fn finish(self, mut history: History) -> WorkflowOutputs {
+        for (node_id, output) in self.outputs {
+            history
+                .outputs
+                .entry(node_id)
+                .or_insert_with(|| output.into());
+        }
+        WorkflowOutputs {
+            prompt_id: self.prompt_id,
+            history,
+        }
+    }
+}
+
+/// The naming of the local files written by
+/// [`ComfyUIClient::download_outputs`](crate::ComfyUIClient::download_outputs).
+///
+/// Whatever the strategy, an existing file is never overwritten: a numeric
+/// suffix is appended to the file name instead, e.g. `image_1.png`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum NamingStrategy {
+    /// Keeps the file name and the subfolder on the server, e.g.
+    /// `dest/subfolder/ComfyUI_00001_.png`.
+    #[default]
+    KeepSubfolder,
+    /// Keeps the file name, writing all files directly into the destination
+    /// directory, e.g. `dest/ComfyUI_00001_.png`.
+    Flatten,
+    /// Prefixes the file name with the identifier of the producing node,
+    /// writing all files directly into the destination directory, e.g.
+    /// `dest/9_ComfyUI_00001_.png`.
+    NodePrefix,
 }
 
 /// Contains an optional list of image file information.
@@ -55,6 +1093,68 @@ pub struct Images {
     pub images: Option<Vec<FileInfo>>,
     /// A vector of animated file information objects, if available.
     pub gifs: Option<Vec<FileInfo>>,
+    /// A vector of audio file information objects, if available.
+    pub audio: Option<Vec<FileInfo>>,
+    /// Additional output data that doesn't fit into predefined categories,
+    /// e.g. `text` or `latents`.
+    #[serde(flatten)]
+    pub others: HashMap<String, Value>,
+}
+
+impl Images {
+    /// Returns the images produced by the node.
+    pub fn images(&self) -> &[FileInfo] {
+        self.images.as_deref().unwrap_or_default()
+    }
+
+    /// Returns the videos (animated files) produced by the node.
+    pub fn videos(&self) -> &[FileInfo] {
+        self.gifs.as_deref().unwrap_or_default()
+    }
+
+    /// Returns the audio files produced by the node.
+    pub fn audio(&self) -> &[FileInfo] {
+        self.audio.as_deref().unwrap_or_default()
+    }
+
+    /// Returns the files of an output without a dedicated field, e.g. the
+    /// `latents` of a `SaveLatent` node.
+    ///
+    /// Returns an empty list if the output is missing or doesn't consist of
+    /// files.
+    pub fn other_files(&self, name: &str) -> Vec<FileInfo> {
+        output_files(&self.others, name)
+    }
+
+    /// Returns an iterator over the texts produced by the node.
+    pub fn texts(&self) -> impl Iterator<Item = &str> {
+        output_texts(&self.others)
+    }
+
+    /// Returns the first image produced by the node, if any.
+    pub fn first_image(&self) -> Option<&FileInfo> {
+        self.images().first()
+    }
+
+    /// Returns an iterator over all files (images, videos and audio)
+    /// produced by the node.
+    pub fn all_files(&self) -> impl Iterator<Item = &FileInfo> {
+        self.images()
+            .iter()
+            .chain(self.videos())
+            .chain(self.audio())
+    }
+}
+
+impl From<ExecutedOutput> for Images {
+    fn from(output: ExecutedOutput) -> Self {
+        Self {
+            images: output.images,
+            gifs: output.gifs,
+            audio: output.audio,
+            others: output.others,
+        }
+    }
 }
 
 /// Represents events emitted by the ComfyUI client during workflow execution.
@@ -67,6 +1167,54 @@ pub enum Event {
     Comfy(ComfyEvent),
     /// `Connection` events relate to WebSocket connection management
     Connection(ConnectionEvent),
+    /// `Preview` events carry live previews of the image being sampled,
+    /// received as binary websocket messages
+    Preview(PreviewFrame),
+}
+
+/// A live preview of the image being sampled, e.g. by a `KSampler` node.
+///
+/// Previews are only sent if the server is started with a preview method
+/// such as `--preview-method auto`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PreviewFrame {
+    /// The format of the encoded image.
+    pub format: PreviewFormat,
+    /// The encoded image data.
+    pub data: Bytes,
+    /// The node and prompt the preview belongs to, sent by servers if the
+    /// client announced `supports_preview_metadata` in its feature flags.
+    pub metadata: Option<PreviewMetadata>,
+}
+
+/// The metadata of a [`PreviewFrame`].
+#[derive(Clone, Deserialize, Debug, PartialEq, Eq)]
+pub struct PreviewMetadata {
+    /// The identifier of the node producing the preview.
+    pub node_id: String,
+    /// The identifier of the node displaying the preview, which differs for
+    /// nodes expanded from subgraphs.
+    #[serde(default)]
+    pub display_node_id: Option<String>,
+    /// The identifier of the parent node, for nodes expanded from subgraphs.
+    #[serde(default)]
+    pub parent_node_id: Option<String>,
+    /// The identifier of the node in the original workflow.
+    #[serde(default)]
+    pub real_node_id: Option<String>,
+    /// The ID of the prompt the preview belongs to.
+    pub prompt_id: String,
+    /// The MIME type of the image, e.g. `image/png`.
+    pub image_type: String,
+}
+
+/// The format of a [`PreviewFrame`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PreviewFormat {
+    /// A JPEG image.
+    Jpeg,
+    /// A PNG image.
+    Png,
 }
 
 /// Represents events emitted by the ComfyUI service during workflow execution.
@@ -75,7 +1223,7 @@ pub enum Event {
 /// of a workflow, from queuing to completion. Each variant contains specific
 /// data relevant to that event type. The `Unknown` variant captures any
 /// unrecognized events from the API.
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Clone, Serialize, Deserialize, Debug)]
 #[serde(tag = "type")]
 #[serde(rename_all = "snake_case")]
 pub enum ComfyEvent {
@@ -91,6 +1239,11 @@ pub enum ComfyEvent {
         /// Data payload containing current and maximum progress values.
         data: ProgressEventData,
     },
+    /// An event reporting the progress of every node of a prompt.
+    ProgressState {
+        /// Data payload containing the progress of each node.
+        data: ProgressStateEventData,
+    },
     /// An event indicating that a node has completed execution along with its
     /// output data.
     Executed {
@@ -132,10 +1285,292 @@ pub enum ComfyEvent {
         /// workflow.
         data: ExecutionSuccessEventData,
     },
+    /// An event carrying new log entries of the server, only sent after
+    /// subscribing via
+    /// [`ComfyUIClient::subscribe_logs`](crate::ComfyUIClient::subscribe_logs).
+    Logs {
+        /// Data payload containing the new log entries.
+        data: LogsEventData,
+    },
     /// An unknown event type that encapsulates raw JSON data for events not
     /// explicitly defined.
     #[serde(skip)]
     Unknown(Value),
+    /// An unknown event type kept as raw JSON, without parsing its data.
+    ///
+    /// Delivered instead of [`ComfyEvent::Unknown`] if enabled via
+    /// [`ClientBuilder::raw_unknown_events`](crate::ClientBuilder::raw_unknown_events).
+    #[serde(skip)]
+    Raw(RawEvent),
+}
+
+impl ComfyEvent {
+    /// Returns the ID of the prompt the event belongs to.
+    ///
+    /// For unknown events, the `prompt_id` field of their data is used if
+    /// present, which covers the events of many custom nodes.
+    ///
+    /// # Returns
+    ///
+    /// The prompt ID, or `None` for events not tied to a prompt such as
+    /// `status`.
+    pub fn prompt_id(&self) -> Option<&str> {
+        match self {
+            ComfyEvent::Status { .. } | ComfyEvent::Logs { .. } => None,
+            ComfyEvent::Progress { data } => data.prompt_id.as_deref(),
+            ComfyEvent::ProgressState { data } => Some(&data.prompt_id),
+            ComfyEvent::Executed { data } => Some(&data.prompt_id),
+            ComfyEvent::Executing { data } => Some(&data.prompt_id),
+            ComfyEvent::ExecutionStart { data } => Some(&data.prompt_id),
+            ComfyEvent::ExecutionError { data } => Some(&data.prompt_id),
+            ComfyEvent::ExecutionCached { data } => Some(&data.prompt_id),
+            ComfyEvent::ExecutionInterrupted { data } => Some(&data.prompt_id),
+            ComfyEvent::ExecutionSuccess { data } => Some(&data.prompt_id),
+            ComfyEvent::Unknown(value) => value["data"]["prompt_id"].as_str(),
+            ComfyEvent::Raw(raw) => raw.prompt_id.as_deref(),
+        }
+    }
+
+    /// Decodes an event from the JSON of a websocket message, like the
+    /// client does for received messages.
+    ///
+    /// The JSON is parsed once: the data of known events is deserialized
+    /// directly into the payload of their variant, without an intermediate
+    /// [`Value`]. Events of unknown types, and events whose data can't be
+    /// decoded, become a [`ComfyEvent::Unknown`], or a [`ComfyEvent::Raw`] if
+    /// enabled and the type is unknown.
+    ///
+    /// # Parameters
+    ///
+    /// - `json`: The JSON of the message.
+    /// - `options`: The [`DecodeOptions`], e.g. as set via the
+    ///   [`ClientBuilder`](crate::ClientBuilder).
+    ///
+    /// # Returns
+    ///
+    /// The event, an error if `json` isn't valid JSON, or an
+    /// [`EventDecodeError`] in strict mode if the event can't be decoded.
+    pub fn from_json(json: &str, options: DecodeOptions) -> ClientResult<Self> {
+        match Self::decode(json, options) {
+            Err(ClientError::SerdeJson(err)) if options.lenient_numbers => {
+                let Some(text) = replace_non_finite_numbers(json) else {
+                    return Err(err.into());
+                };
+                warn!("replaced non-finite numbers in websocket message with null");
+                Self::decode(&text, options)
+            }
+            result => result,
+        }
+    }
+
+    /// Decodes an event from JSON, see [`ComfyEvent::from_json`].
+    fn decode(json: &str, options: DecodeOptions) -> ClientResult<Self> {
+        let envelope = match serde_json::from_str::<Envelope<'_>>(json) {
+            Ok(envelope) => envelope,
+            Err(err) => return Self::undecodable(json, None, err, options),
+        };
+        let event = match envelope.event_type.as_ref() {
+            "status" => envelope.data(options).map(|data| ComfyEvent::Status {
+                data,
+                sid: envelope.sid.clone(),
+            }),
+            "progress" => envelope
+                .data(options)
+                .map(|data| ComfyEvent::Progress { data }),
+            "progress_state" => envelope
+                .data(options)
+                .map(|data| ComfyEvent::ProgressState { data }),
+            "executed" => envelope
+                .data(options)
+                .map(|data| ComfyEvent::Executed { data }),
+            "executing" => envelope
+                .data(options)
+                .map(|data| ComfyEvent::Executing { data }),
+            "execution_start" => envelope
+                .data(options)
+                .map(|data| ComfyEvent::ExecutionStart { data }),
+            "execution_error" => envelope
+                .data(options)
+                .map(|data| ComfyEvent::ExecutionError { data }),
+            "execution_cached" => envelope
+                .data(options)
+                .map(|data| ComfyEvent::ExecutionCached { data }),
+            "execution_interrupted" => envelope
+                .data(options)
+                .map(|data| ComfyEvent::ExecutionInterrupted { data }),
+            "execution_success" => envelope
+                .data(options)
+                .map(|data| ComfyEvent::ExecutionSuccess { data }),
+            "logs" => envelope.data(options).map(|data| ComfyEvent::Logs { data }),
+            _ if options.raw_unknown => {
+                let prompt_id = envelope.data.and_then(|data| {
+                    serde_json::from_str::<PromptIdProbe>(data.get())
+                        .ok()?
+                        .prompt_id
+                });
+                return Ok(ComfyEvent::Raw(RawEvent {
+                    event_type: envelope.event_type.into_owned(),
+                    prompt_id,
+                    json: RawValue::from_string(json.to_string())?,
+                }));
+            }
+            _ => Err(serde::de::Error::unknown_variant(&envelope.event_type, &[])),
+        };
+        event.or_else(|err| Self::undecodable(json, Some(envelope.event_type), err, options))
+    }
+
+    /// Handles an event which can't be decoded into a known variant.
+    ///
+    /// Parses the JSON into a [`Value`], which becomes a
+    /// [`ComfyEvent::Unknown`], or the raw JSON of an [`EventDecodeError`] in
+    /// strict mode.
+    fn undecodable(
+        json: &str, type_field: Option<Cow<'_, str>>, err: serde_json::Error,
+        options: DecodeOptions,
+    ) -> ClientResult<Self> {
+        let value = serde_json::from_str::<Value>(json)?;
+        if !options.strict {
+            return Ok(ComfyEvent::Unknown(value));
+        }
+        Err(EventDecodeError {
+            type_field: type_field
+                .map(Cow::into_owned)
+                .or_else(|| value["type"].as_str().map(ToOwned::to_owned)),
+            serde_error: err,
+            raw: value,
+        }
+        .into())
+    }
+}
+
+/// Options for decoding websocket events, see [`ComfyEvent::from_json`].
+///
+/// The client uses the options set via the
+/// [`ClientBuilder`](crate::ClientBuilder).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DecodeOptions {
+    /// Whether to report undecodable events as errors, see
+    /// [`ClientBuilder::strict_event_decoding`](crate::ClientBuilder::strict_event_decoding).
+    pub strict: bool,
+    /// Whether to keep events of unknown types as raw JSON, see
+    /// [`ClientBuilder::raw_unknown_events`](crate::ClientBuilder::raw_unknown_events).
+    pub raw_unknown: bool,
+    /// Whether to tolerate malformed numbers, see
+    /// [`ClientBuilder::lenient_numbers`](crate::ClientBuilder::lenient_numbers).
+    pub lenient_numbers: bool,
+}
+
+/// The type, data and session ID of an event, with the data borrowed from
+/// its JSON.
+#[derive(Deserialize)]
+struct Envelope<'a> {
+    #[serde(rename = "type", borrow)]
+    event_type: Cow<'a, str>,
+    #[serde(default, borrow)]
+    data: Option<&'a RawValue>,
+    #[serde(default)]
+    sid: Option<String>,
+}
+
+impl Envelope<'_> {
+    /// Deserializes the data into the payload of a known event.
+    ///
+    /// If that fails in lenient mode, the malformed unsigned integers of the
+    /// data are converted lossily and the data is deserialized again.
+    fn data<T: DeserializeOwned>(&self, options: DecodeOptions) -> serde_json::Result<T> {
+        let Some(data) = self.data else {
+            return Err(serde::de::Error::missing_field("data"));
+        };
+        let err = match serde_json::from_str(data.get()) {
+            Ok(data) => return Ok(data),
+            Err(err) => err,
+        };
+        let pointers = lenient::unsigned_integers(&self.event_type);
+        if !options.lenient_numbers || pointers.is_empty() {
+            return Err(err);
+        }
+        let mut value = serde_json::from_str::<Value>(data.get())?;
+        lenient::convert_unsigned_integers(&mut value, pointers);
+        serde_json::from_value(value)
+    }
+}
+
+/// Replaces the non-standard `NaN`, `Infinity` and `-Infinity` literals
+/// emitted by Python's JSON encoder with `null`.
+///
+/// Returns `None` if the text contains no such literal outside of strings.
+fn replace_non_finite_numbers(text: &str) -> Option<String> {
+    const LITERALS: [&str; 3] = ["-Infinity", "Infinity", "NaN"];
+
+    let mut output = String::with_capacity(text.len());
+    let mut replaced = false;
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut rest = text;
+
+    while let Some(c) = rest.chars().next() {
+        if !in_string {
+            if let Some(literal) = LITERALS.iter().find(|literal| rest.starts_with(*literal)) {
+                output.push_str("null");
+                rest = &rest[literal.len()..];
+                replaced = true;
+                continue;
+            }
+        }
+        if in_string {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                in_string = false;
+            }
+        } else if c == '"' {
+            in_string = true;
+        }
+        output.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+
+    replaced.then_some(output)
+}
+
+/// The `prompt_id` of the data of an event, ignoring all other fields.
+#[derive(Deserialize)]
+struct PromptIdProbe {
+    #[serde(default)]
+    prompt_id: Option<String>,
+}
+
+/// An event of an unknown type, kept as raw JSON.
+///
+/// Avoids parsing the data of high-frequency events of custom nodes, e.g.
+/// the system monitor of crystools, which the application isn't interested
+/// in. See
+/// [`ClientBuilder::raw_unknown_events`](crate::ClientBuilder::raw_unknown_events).
+#[derive(Clone, Debug)]
+pub struct RawEvent {
+    event_type: String,
+    prompt_id: Option<String>,
+    json: Box<RawValue>,
+}
+
+impl RawEvent {
+    /// Returns the type of the event, e.g. `crystools.monitor`.
+    pub fn event_type(&self) -> &str {
+        &self.event_type
+    }
+
+    /// Returns the raw JSON of the whole event.
+    pub fn json(&self) -> &str {
+        self.json.get()
+    }
+
+    /// Parses the JSON of the event, e.g. to convert it into a
+    /// [`ComfyEvent::Unknown`].
+    pub fn to_value(&self) -> serde_json::Result<Value> {
+        serde_json::from_str(self.json.get())
+    }
 }
 
 /// Represents events that are not part of the standard ComfyUI API
@@ -166,6 +1601,43 @@ pub enum ConnectionEvent {
     /// Indicates that an error occurred in the WebSocket communication channel
     /// while trying to receive messages from the ComfyUI server.
     WSReceiveError(tungstenite::Error),
+
+    /// Event indicating that a Ping frame wasn't answered in time.
+    ///
+    /// Only emitted if a Ping interval is configured via
+    /// [`ClientBuilder::ws_ping_interval`](crate::ClientBuilder::ws_ping_interval).
+    /// The connection is considered lost and reestablished afterwards.
+    WSPingTimeout,
+
+    /// Event indicating that no message was received within the idle timeout.
+    ///
+    /// Only emitted if an idle timeout is configured via
+    /// [`ClientBuilder::ws_idle_timeout`](crate::ClientBuilder::ws_idle_timeout),
+    /// and repeated for every further timeout without messages. The connection
+    /// is reestablished afterwards if enabled via
+    /// [`ClientBuilder::reconnect_on_idle`](crate::ClientBuilder::reconnect_on_idle).
+    WSIdle,
+}
+
+/// The state of the websocket connection, see
+/// [`ConnectionHandle`](crate::ConnectionHandle).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ConnectionState {
+    /// The initial connection is not established yet, as it is deferred via
+    /// [`ClientBuilder::lazy_connect`](crate::ClientBuilder::lazy_connect) or
+    /// still in progress.
+    Connecting,
+    /// The connection is established.
+    Connected,
+    /// The connection was lost and is being reestablished.
+    Reconnecting {
+        /// The number of the current reconnection attempt, starting at 1.
+        attempt: u32,
+    },
+    /// The connection is closed for good, e.g. because reconnection is
+    /// disabled or the [`EventStream`](crate::EventStream) was dropped.
+    Closed,
 }
 
 /// Event payload for a status event, containing execution information.
@@ -201,21 +1673,168 @@ pub struct ProgressEventData {
     pub value: usize,
     /// The maximum progress value representing the total number of steps.
     pub max: usize,
+    /// The prompt ID associated with the progress, if reported.
+    #[serde(default)]
+    pub prompt_id: Option<String>,
+    /// The identifier of the node making progress, if reported.
+    #[serde(default)]
+    pub node: Option<String>,
+}
+
+/// Event payload reporting the progress of every node of a prompt.
+///
+/// This structure is received by newer ComfyUI versions whenever the progress
+/// of a node changes, which allows showing the progress of a whole pipeline
+/// rather than only the executing node.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct ProgressStateEventData {
+    /// The prompt ID associated with the progress.
+    pub prompt_id: String,
+    /// The progress of the nodes keyed by node identifier.
+    pub nodes: HashMap<String, NodeProgress>,
+}
+
+/// The progress of a single node, as reported by the `progress_state` event.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct NodeProgress {
+    /// The current progress value.
+    pub value: f64,
+    /// The maximum progress value.
+    pub max: f64,
+    /// The execution state of the node.
+    pub state: NodeState,
+    /// The identifier of the node.
+    pub node_id: String,
+    /// The identifier of the node displayed in the UI, if it differs from
+    /// `node_id`, e.g. for nodes within subgraphs.
+    pub display_node_id: Option<String>,
+    /// The identifier of the parent node, if the node was created by another
+    /// node during execution.
+    pub parent_node_id: Option<String>,
+    /// The identifier of the node in the original prompt, if any.
+    pub real_node_id: Option<String>,
+}
+
+/// The execution state of a node.
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum NodeState {
+    /// The node is waiting for execution.
+    Pending,
+    /// The node is executing.
+    Running,
+    /// The node has finished executing.
+    Finished,
+    /// The execution of the node failed.
+    Error,
+}
+
+/// Represents the output of an executed node.
+///
+/// Contains the results produced by a node in the workflow after successful
+/// execution. This can include generated or processed images in the `images`
+/// field, animated files in the `gifs` field, as well as other arbitrary output
+/// data in the `others` map.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct ExecutedOutput {
+    /// Optional list of image file information objects generated or processed
+    /// by the node.
+    pub images: Option<Vec<FileInfo>>,
+    /// Optional list of animated file information objects generated by the
+    /// node.
+    pub gifs: Option<Vec<FileInfo>>,
+    /// Optional list of audio file information objects generated by the
+    /// node.
+    pub audio: Option<Vec<FileInfo>>,
+    /// Additional output data that doesn't fit into predefined categories.
+    #[serde(flatten)]
+    pub others: HashMap<String, Value>,
+}
+
+impl ExecutedOutput {
+    /// Returns the images produced by the node.
+    pub fn images(&self) -> &[FileInfo] {
+        self.images.as_deref().unwrap_or_default()
+    }
+
+    /// Returns the videos (animated files) produced by the node.
+    pub fn videos(&self) -> &[FileInfo] {
+        self.gifs.as_deref().unwrap_or_default()
+    }
+
+    /// Returns the audio files produced by the node.
+    pub fn audio(&self) -> &[FileInfo] {
+        self.audio.as_deref().unwrap_or_default()
+    }
+
+    /// Returns the files of an output without a dedicated field, e.g. the
+    /// `latents` of a `SaveLatent` node.
+    ///
+    /// Returns an empty list if the output is missing or doesn't consist of
+    /// files.
+    pub fn other_files(&self, name: &str) -> Vec<FileInfo> {
+        output_files(&self.others, name)
+    }
+
+    /// Returns an iterator over the texts produced by the node.
+    pub fn texts(&self) -> impl Iterator<Item = &str> {
+        output_texts(&self.others)
+    }
+
+    /// Returns the first image produced by the node, if any.
+    pub fn first_image(&self) -> Option<&FileInfo> {
+        self.images().first()
+    }
+
+    /// Returns an iterator over all files (images, videos and audio)
+    /// produced by the node.
+    pub fn all_files(&self) -> impl Iterator<Item = &FileInfo> {
+        self.images()
+            .iter()
+            .chain(self.videos())
+            .chain(self.audio())
+    }
+}
+
+/// Extracts the texts from the `text` entry of a node output, which is either
+/// a single string or a list of strings.
+fn output_texts(others: &HashMap<String, Value>) -> impl Iterator<Item = &str> {
+    let texts = match others.get("text") {
+        Some(Value::Array(texts)) => texts.as_slice(),
+        Some(text) => std::slice::from_ref(text),
+        None => &[],
+    };
+    texts.iter().filter_map(Value::as_str)
 }
 
-/// Represents the output of an executed node.
-///
-/// Contains the results produced by a node in the workflow after successful
-/// execution. This can include generated or processed images in the `images`
-/// field, as well as other arbitrary output data in the `others` map.
-#[derive(Clone, Serialize, Deserialize, Debug)]
-pub struct ExecutedOutput {
-    /// Optional list of image file information objects generated or processed
-    /// by the node.
-    pub images: Option<Vec<FileInfo>>,
-    /// Additional output data that doesn't fit into predefined categories.
-    #[serde(flatten)]
-    pub others: HashMap<String, Value>,
+/// Extracts the files from an entry of a node output, skipping the elements
+/// which aren't files.
+fn output_files(others: &HashMap<String, Value>, name: &str) -> Vec<FileInfo> {
+    match others.get(name) {
+        Some(Value::Array(files)) => files
+            .iter()
+            .filter_map(|file| FileInfo::deserialize(file).ok())
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Compares node identifiers by their `:` separated segments numerically, so
+/// that the nodes of a subgraph follow their parent node. Identifiers with a
+/// non-numeric segment come after all numeric ones, in lexicographic order.
+pub(crate) fn compare_node_ids(a: &str, b: &str) -> Ordering {
+    fn key(id: &str) -> Option<Vec<u64>> {
+        id.split(':').map(|segment| segment.parse().ok()).collect()
+    }
+
+    match (key(a), key(b)) {
+        (Some(key_a), Some(key_b)) => key_a.cmp(&key_b),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+    .then_with(|| a.cmp(b))
 }
 
 /// Event payload for a completed execution, including the node identifier,
@@ -356,6 +1975,41 @@ pub struct ExecutionSuccessEventData {
     pub prompt_id: String,
 }
 
+/// Data for the logs event.
+///
+/// This structure is received while subscribed to the logs of the server,
+/// whenever new output is written to its console.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct LogsEventData {
+    /// The new log entries, oldest first.
+    pub entries: Vec<LogEntry>,
+    /// The size of the terminal of the server, if reported.
+    pub size: Option<TerminalSize>,
+}
+
+/// An estimation of the offset between the clocks of the client and the
+/// server.
+///
+/// Obtained from [`EventStream::clock_skew`](crate::EventStream::clock_skew).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ClockSkew {
+    /// The offset of the local clock relative to the server clock in
+    /// milliseconds, including the minimal observed delivery latency. Positive
+    /// if the local clock is ahead of the server clock.
+    pub offset_ms: i64,
+    /// The number of samples the estimation is based on.
+    pub samples: usize,
+}
+
+impl ClockSkew {
+    /// Converts a server timestamp in milliseconds, as found in event
+    /// payloads, to the corresponding local time.
+    pub fn to_local_time(&self, timestamp_ms: u64) -> SystemTime {
+        let local_ms = timestamp_ms as i64 + self.offset_ms;
+        UNIX_EPOCH + Duration::from_millis(local_ms.max(0) as u64)
+    }
+}
+
 /// `Prompt` param for
 /// [`ComfyUIClient::post_prompt`](crate::ComfyUIClient::post_prompt).
 ///
@@ -394,11 +2048,332 @@ impl<'a> From<&'a Value> for Prompt<'a> {
     }
 }
 
+/// A namespace isolating the outputs of a prompt on the server.
+///
+/// Applying a namespace to a prompt prefixes the `filename_prefix` input of
+/// every output node (e.g. `SaveImage`), so that ComfyUI stores the resulting
+/// files under a dedicated subfolder of its output directory. This keeps the
+/// outputs of concurrent jobs or tenants from interleaving.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OutputNamespace(String);
+
+impl OutputNamespace {
+    /// Creates a new [`OutputNamespace`], such as a job ID or a tenant name.
+    ///
+    /// Leading and trailing slashes are trimmed, nested namespaces like
+    /// `tenant/job` are allowed. A namespace that is empty after trimming
+    /// stands for the output directory itself: applying it leaves the prompt
+    /// untouched.
+    pub fn new(namespace: impl Into<String>) -> Self {
+        let namespace = namespace.into().replace('\\', "/");
+        Self(namespace.trim_matches('/').to_string())
+    }
+
+    /// Returns the namespace as a string slice.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Prefixes the `filename_prefix` input of every node in the prompt with
+    /// the namespace.
+    ///
+    /// Inputs linked to other nodes are left untouched, as well as all inputs
+    /// if the namespace is empty.
+    pub fn apply(&self, prompt: &mut Value) {
+        if self.0.is_empty() {
+            return;
+        }
+        let Some(nodes) = prompt.as_object_mut() else {
+            return;
+        };
+        for node in nodes.values_mut() {
+            if let Some(Value::String(prefix)) = node.pointer_mut("/inputs/filename_prefix") {
+                *prefix = format!("{}/{}", self.0, prefix);
+            }
+        }
+    }
+
+    /// Checks whether the file is stored within the namespace.
+    ///
+    /// Both `/` and `\` are accepted as separators in the subfolder, as
+    /// reported by servers running on Windows.
+    pub fn contains(&self, file: &FileInfo) -> bool {
+        if self.0.is_empty() {
+            return true;
+        }
+        let subfolder = file.subfolder.replace('\\', "/");
+        subfolder
+            .strip_prefix(&self.0)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+    }
+}
+
+/// The result of resolving a requested model name against the models
+/// available on the server.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ModelMatch {
+    /// The name of the matched model, as known by the server.
+    pub name: String,
+    /// The similarity between the requested and the matched name, from `0.0`
+    /// (unrelated) to `1.0` (identical).
+    pub confidence: f64,
+}
+
+impl ModelMatch {
+    /// Finds the available model name closest to the requested one.
+    ///
+    /// Names are compared case-insensitively, both as a whole and by their
+    /// file name only, so that models stored in different subfolders are
+    /// still matched. An identical name always wins with a confidence of
+    /// `1.0`.
+    ///
+    /// # Parameters
+    ///
+    /// - `requested`: The model name referenced by a workflow.
+    /// - `available`: The model names available on the server.
+    /// - `min_confidence`: The minimum confidence for a match to be returned.
+    ///
+    /// # Returns
+    ///
+    /// The best [`ModelMatch`], or `None` if no name reaches `min_confidence`.
+    pub fn find<'a>(
+        requested: &str, available: impl IntoIterator<Item = &'a str>, min_confidence: f64,
+    ) -> Option<Self> {
+        let mut best: Option<Self> = None;
+        for name in available {
+            let confidence = if name == requested {
+                1.0
+            } else {
+                model_name_similarity(requested, name)
+            };
+            if confidence >= min_confidence
+                && best
+                    .as_ref()
+                    .is_none_or(|best| confidence > best.confidence)
+            {
+                best = Some(Self {
+                    name: name.to_string(),
+                    confidence,
+                });
+            }
+        }
+        best
+    }
+}
+
+/// Computes the similarity of two model names, ignoring case, path separators
+/// and folders.
+fn model_name_similarity(a: &str, b: &str) -> f64 {
+    let normalize = |name: &str| name.replace('\\', "/").to_lowercase();
+    let (a, b) = (normalize(a), normalize(b));
+    let file_name = |name: &str| name.rsplit('/').next().unwrap_or_default().to_string();
+    // Identical names differing only in case rank right below exact matches.
+    let similarity = |a: &str, b: &str| {
+        if a == b {
+            0.99
+        } else {
+            let len = a.chars().count().max(b.chars().count());
+            1.0 - levenshtein(a, b) as f64 / len as f64
+        }
+    };
+    similarity(&a, &b).max(similarity(&file_name(&a), &file_name(&b)) * 0.95)
+}
+
+/// Computes the Levenshtein edit distance between two strings.
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut row = (0..=b.len()).collect::<Vec<_>>();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = if ca == *cb {
+                diagonal
+            } else {
+                diagonal.min(above).min(row[j]) + 1
+            };
+            diagonal = above;
+        }
+    }
+    row[b.len()]
+}
+
+/// A JSON object deserialized into its entries, preserving their order.
+pub(crate) struct Entries<V>(pub(crate) Vec<(String, V)>);
+
+impl<'de, V: Deserialize<'de>> Deserialize<'de> for Entries<V> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct EntriesVisitor<V>(PhantomData<V>);
+
+        impl<'de, V: Deserialize<'de>> Visitor<'de> for EntriesVisitor<V> {
+            type Value = Entries<V>;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+                formatter.write_str("a map")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                let mut entries = Vec::with_capacity(map.size_hint().unwrap_or_default());
+                while let Some(entry) = map.next_entry()? {
+                    entries.push(entry);
+                }
+                Ok(Entries(entries))
+            }
+        }
+
+        deserializer.deserialize_map(EntriesVisitor(PhantomData))
+    }
+}
+
+/// Tolerant deserializers for fields emitted inconsistently by extensions.
+///
+/// Some extensions emit floats, negative numbers or numeric strings where
+/// ComfyUI itself emits unsigned integers. In lenient mode, such values in the
+/// data of events are converted lossily and a warning is logged, instead of
+/// failing to decode the whole event, see
+/// [`ClientBuilder::lenient_numbers`](crate::ClientBuilder::lenient_numbers).
+/// Similarly, schema fields of custom nodes are decoded on a best-effort basis.
+pub(crate) mod lenient {
+    use log::warn;
+    use serde::{
+        Deserialize, Deserializer,
+        de::{Error, Visitor},
+    };
+    use serde_json::Value;
+    use std::fmt;
+
+    /// Returns the JSON pointers to the unsigned integers of the data of
+    /// events of the given type.
+    pub(super) fn unsigned_integers(event_type: &str) -> &'static [&'static str] {
+        match event_type {
+            "status" => &["/status/exec_info/queue_remaining"],
+            "progress" => &["/value", "/max"],
+            "execution_start" | "execution_cached" => &["/timestamp"],
+            _ => &[],
+        }
+    }
+
+    /// Converts the malformed unsigned integers at the given JSON pointers of
+    /// the data of an event lossily.
+    ///
+    /// Floats are rounded, negative numbers saturate to zero, numeric strings
+    /// are parsed and `null` becomes zero. Other values are left as is.
+    pub(super) fn convert_unsigned_integers(data: &mut Value, pointers: &[&str]) {
+        for pointer in pointers {
+            let Some(value) = data.pointer_mut(pointer) else {
+                continue;
+            };
+            let n = match value {
+                Value::Number(n) if n.is_u64() => continue,
+                Value::Number(n) => n.as_f64(),
+                Value::String(s) => s.trim().parse::<f64>().ok(),
+                Value::Null => Some(f64::NAN),
+                _ => None,
+            };
+            let Some(n) = n else {
+                continue;
+            };
+            // Float to integer casts saturate, and map NaN to zero.
+            let converted = n.round() as u64;
+            warn!(value:% = value, converted; "lossy conversion of number in event payload");
+            *value = converted.into();
+        }
+    }
+
+    /// Decodes a signed integer, accepting integral floats such as `-1.0`
+    /// which the server reports for explicitly numbered prompts.
+    ///
+    /// Floats with a fractional part are rejected instead of being rounded.
+    pub(super) fn i64<'de, D: Deserializer<'de>>(deserializer: D) -> Result<i64, D::Error> {
+        struct I64Visitor;
+
+        impl Visitor<'_> for I64Visitor {
+            type Value = i64;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+                formatter.write_str("an integer")
+            }
+
+            fn visit_i64<E: Error>(self, v: i64) -> Result<i64, E> {
+                Ok(v)
+            }
+
+            fn visit_u64<E: Error>(self, v: u64) -> Result<i64, E> {
+                i64::try_from(v).map_err(|_| E::custom(format!("integer out of range: {v}")))
+            }
+
+            fn visit_f64<E: Error>(self, v: f64) -> Result<i64, E> {
+                let converted = v as i64;
+                if converted as f64 != v {
+                    return Err(E::custom(format!("invalid integer: {v}")));
+                }
+                Ok(converted)
+            }
+        }
+
+        deserializer.deserialize_any(I64Visitor)
+    }
+
+    /// Decodes an optional float, ignoring values which aren't numbers.
+    pub(super) fn opt_f64<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<f64>, D::Error> {
+        Ok(match Value::deserialize(deserializer)? {
+            Value::Number(n) => n.as_f64(),
+            Value::String(s) => s.trim().parse().ok(),
+            _ => None,
+        })
+    }
+
+    /// Decodes a list of strings, converting other values to their JSON text.
+    pub(super) fn strings<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<String>, D::Error> {
+        let values = match Value::deserialize(deserializer)? {
+            Value::Array(values) => values,
+            Value::Null => Vec::new(),
+            value => vec![value],
+        };
+        Ok(values
+            .into_iter()
+            .map(|value| match value {
+                Value::String(s) => s,
+                value => value.to_string(),
+            })
+            .collect())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use serde_json::json;
 
+    #[test]
+    fn test_retry_policy_backoff() {
+        let policy = RetryPolicy {
+            max_attempts: 5,
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(3),
+        };
+        assert_eq!(policy.backoff(1), Duration::from_secs(1));
+        assert_eq!(policy.backoff(2), Duration::from_secs(2));
+        assert_eq!(policy.backoff(3), Duration::from_secs(3));
+        assert_eq!(policy.backoff(40), Duration::from_secs(3));
+    }
+
+    #[test]
+    fn test_poll_options_interval() {
+        let options = PollOptions {
+            initial_interval: Duration::from_millis(500),
+            max_interval: Duration::from_secs(1),
+        };
+        assert_eq!(options.interval(1), Duration::from_millis(500));
+        assert_eq!(options.interval(2), Duration::from_secs(1));
+        assert_eq!(options.interval(40), Duration::from_secs(1));
+    }
+
     /// Tests serialization of different event types.
     #[test]
     fn test_serialize_event() {
@@ -444,4 +2419,687 @@ mod tests {
             })
         );
     }
+
+    /// Tests deserialization of log events.
+    #[test]
+    fn test_deserialize_logs_event() {
+        let ev = serde_json::from_value::<ComfyEvent>(json!({
+            "type": "logs",
+            "data": {
+                "entries": [{"t": "2025-03-01T12:00:00.000000", "m": "Traceback"}],
+                "size": {"cols": 80, "rows": 24}
+            }
+        }))
+        .unwrap();
+        let ComfyEvent::Logs { data } = ev else {
+            panic!("unexpected event: {ev:?}");
+        };
+        assert_eq!(data.entries[0].message, "Traceback");
+        assert_eq!(data.size.and_then(|size| size.cols), Some(80));
+    }
+
+    /// Tests deserialization of progress state events.
+    #[test]
+    fn test_deserialize_progress_state_event() {
+        let ev = serde_json::from_value::<ComfyEvent>(json!({
+            "type": "progress_state",
+            "data": {
+                "prompt_id": "xxxxxx",
+                "nodes": {
+                    "3": {
+                        "value": 5.0,
+                        "max": 20.0,
+                        "state": "running",
+                        "node_id": "3",
+                        "prompt_id": "xxxxxx",
+                        "display_node_id": "3",
+                        "parent_node_id": null,
+                        "real_node_id": "3"
+                    },
+                    "9": {"value": 0, "max": 1, "state": "pending", "node_id": "9"}
+                }
+            }
+        }))
+        .unwrap();
+        let ComfyEvent::ProgressState { data } = ev else {
+            panic!("unexpected event: {ev:?}");
+        };
+        assert_eq!(data.prompt_id, "xxxxxx");
+        assert_eq!(data.nodes["3"].state, NodeState::Running);
+        assert_eq!(data.nodes["3"].value, 5.0);
+        assert_eq!(data.nodes["9"].state, NodeState::Pending);
+        assert_eq!(data.nodes["9"].display_node_id, None);
+    }
+
+    /// Tests that every known event type decodes into its variant.
+    #[test]
+    fn test_decode_known_events() {
+        // Matching exhaustively is a reminder to add a sample for each new
+        // variant.
+        fn event_type(ev: &ComfyEvent) -> &'static str {
+            match ev {
+                ComfyEvent::Status { .. } => "status",
+                ComfyEvent::Progress { .. } => "progress",
+                ComfyEvent::ProgressState { .. } => "progress_state",
+                ComfyEvent::Executed { .. } => "executed",
+                ComfyEvent::Executing { .. } => "executing",
+                ComfyEvent::ExecutionStart { .. } => "execution_start",
+                ComfyEvent::ExecutionError { .. } => "execution_error",
+                ComfyEvent::ExecutionCached { .. } => "execution_cached",
+                ComfyEvent::ExecutionInterrupted { .. } => "execution_interrupted",
+                ComfyEvent::ExecutionSuccess { .. } => "execution_success",
+                ComfyEvent::Logs { .. } => "logs",
+                ComfyEvent::Unknown(_) | ComfyEvent::Raw(_) => "unknown",
+            }
+        }
+
+        let samples = [
+            json!({"type": "status", "data": {"status": {"exec_info": {"queue_remaining": 0}}}}),
+            json!({"type": "progress", "data": {"value": 1, "max": 20}}),
+            json!({"type": "progress_state", "data": {"prompt_id": "p", "nodes": {}}}),
+            json!({"type": "executed", "data": {"node": "9", "prompt_id": "p", "output": null}}),
+            json!({"type": "executing", "data": {"node": null, "prompt_id": "p"}}),
+            json!({"type": "execution_start", "data": {"prompt_id": "p", "timestamp": 1}}),
+            json!({"type": "execution_error", "data": {
+                "prompt_id": "p", "node_id": "3", "node_type": "KSampler", "executed": [],
+                "exception_message": "boom", "exception_type": "RuntimeError",
+                "traceback": [], "current_inputs": {}, "current_outputs": {}
+            }}),
+            json!({"type": "execution_cached", "data": {"nodes": [], "prompt_id": "p", "timestamp": 1}}),
+            json!({"type": "execution_interrupted", "data": {
+                "prompt_id": "p", "node_id": "3", "node_type": "KSampler", "executed": []
+            }}),
+            json!({"type": "execution_success", "data": {"prompt_id": "p"}}),
+            json!({"type": "logs", "data": {"entries": [{"t": "now", "m": "hello"}], "size": null}}),
+        ];
+        for sample in samples {
+            let ev = ComfyEvent::from_json(
+                &sample.to_string(),
+                DecodeOptions {
+                    strict: true,
+                    ..Default::default()
+                },
+            )
+            .unwrap_or_else(|err| panic!("failed to decode {sample}: {err}"));
+            assert_eq!(event_type(&ev), sample["type"]);
+        }
+    }
+
+    /// Tests the prompt IDs of events.
+    #[test]
+    fn test_event_prompt_id() {
+        let ev = serde_json::from_value::<ComfyEvent>(json!({
+            "type": "progress",
+            "data": {"value": 1, "max": 20, "prompt_id": "xxxxxx", "node": "3"}
+        }))
+        .unwrap();
+        assert_eq!(ev.prompt_id(), Some("xxxxxx"));
+
+        let ev = serde_json::from_value::<ComfyEvent>(json!({
+            "type": "progress",
+            "data": {"value": 1, "max": 20}
+        }))
+        .unwrap();
+        assert_eq!(ev.prompt_id(), None);
+
+        let ev = ComfyEvent::Unknown(json!({
+            "type": "custom",
+            "data": {"prompt_id": "xxxxxx"}
+        }));
+        assert_eq!(ev.prompt_id(), Some("xxxxxx"));
+    }
+
+    #[test]
+    fn test_compare_node_ids() {
+        let mut ids = vec!["10:1", "Save", "2", "10", "10:1:3", "03", "3", "10:x", "1"];
+        ids.sort_by(|a, b| compare_node_ids(a, b));
+        assert_eq!(
+            ids,
+            ["1", "2", "03", "3", "10", "10:1", "10:1:3", "10:x", "Save"]
+        );
+    }
+
+    /// Tests the typed accessors of node outputs in a history.
+    #[test]
+    fn test_history_accessors() {
+        let history = serde_json::from_value::<History>(json!({
+            "outputs": {
+                "12": {
+                    "text": ["hello", "world"]
+                },
+                "9": {
+                    "images": [
+                        {"filename": "a.png", "subfolder": "", "type": "output"}
+                    ]
+                },
+                "10": {
+                    "gifs": [
+                        {"filename": "b.mp4", "subfolder": "videos", "type": "output"}
+                    ]
+                },
+                "11": {
+                    "audio": [
+                        {"filename": "c.flac", "subfolder": "audio", "type": "output"}
+                    ]
+                },
+                "13": {
+                    "latents": [
+                        {"filename": "d.latent", "subfolder": "latents", "type": "output"}
+                    ]
+                }
+            }
+        }))
+        .unwrap();
+
+        assert_eq!(history.first_image().unwrap().filename, "a.png");
+        assert_eq!(history.images().count(), 1);
+        assert_eq!(history.videos().next().unwrap().filename, "b.mp4");
+        assert_eq!(history.texts().collect::<Vec<_>>(), ["hello", "world"]);
+        assert_eq!(history.audio().next().unwrap().filename, "c.flac");
+        assert_eq!(
+            history
+                .all_files()
+                .map(|file| file.filename.as_str())
+                .collect::<Vec<_>>(),
+            ["a.png", "b.mp4", "c.flac"]
+        );
+        assert!(history.output("12").unwrap().first_image().is_none());
+        assert_eq!(
+            history.output("13").unwrap().other_files("latents")[0].filename,
+            "d.latent"
+        );
+        assert!(history.output("12").unwrap().other_files("text").is_empty());
+        assert!(history.status.is_none());
+    }
+
+    #[test]
+    fn test_output_collector() {
+        let event = |value| Event::Comfy(serde_json::from_value(value).unwrap());
+        let mut collector = OutputCollector::new("p");
+        assert!(!collector.observe(&event(json!({
+            "type": "executed",
+            "data": {
+                "node": "9",
+                "prompt_id": "p",
+                "output": {"images": [{"filename": "a.png", "subfolder": "", "type": "temp"}]}
+            }
+        }))));
+        assert!(!collector.observe(&event(json!({
+            "type": "executed",
+            "data": {"node": "12", "prompt_id": "p", "output": {"text": ["hello"]}}
+        }))));
+        assert!(!collector.observe(&event(json!({
+            "type": "executed",
+            "data": {"node": "3", "prompt_id": "other", "output": {"text": ["other"]}}
+        }))));
+        assert!(collector.observe(&event(json!({
+            "type": "execution_success",
+            "data": {"prompt_id": "p", "timestamp": 1}
+        }))));
+        assert_eq!(collector.outputs().len(), 2);
+
+        let history = serde_json::from_value::<History>(json!({
+            "outputs": {
+                "9": {"images": [{"filename": "b.png", "subfolder": "", "type": "output"}]}
+            }
+        }))
+        .unwrap();
+        let outputs = collector.finish(history);
+        assert_eq!(outputs.prompt_id, "p");
+        assert_eq!(outputs.images().next().unwrap().filename, "b.png");
+        assert_eq!(outputs.texts().collect::<Vec<_>>(), ["hello"]);
+    }
+
+    #[test]
+    fn test_history_status_error() {
+        let history = serde_json::from_value::<History>(json!({
+            "outputs": {},
+            "status": {
+                "status_str": "error",
+                "completed": false,
+                "messages": [
+                    ["execution_start", {"prompt_id": "p", "timestamp": 1}],
+                    ["execution_interrupted", {
+                        "prompt_id": "p",
+                        "node_id": "3",
+                        "node_type": "KSampler",
+                        "executed": ["4"],
+                        "timestamp": 2
+                    }]
+                ]
+            }
+        }))
+        .unwrap();
+
+        let status = history.status.unwrap();
+        assert!(!status.completed);
+        assert!(!status.is_success());
+        assert_eq!(status.messages[0].timestamp(), Some(1));
+        assert!(matches!(
+            status.messages[0].to_event(),
+            ComfyEvent::ExecutionStart { data } if data.prompt_id == "p"
+        ));
+        assert!(matches!(
+            status.error(),
+            Some(ClientError::Cancelled(CancellationReason::Interrupted(data))) if data.node_id == "3"
+        ));
+    }
+
+    /// Tests applying an output namespace to a prompt.
+    #[test]
+    fn test_output_namespace() {
+        let namespace = OutputNamespace::new("/tenant/job-1/");
+        assert_eq!(namespace.as_str(), "tenant/job-1");
+
+        let mut prompt = json!({
+            "9": {
+                "inputs": {"filename_prefix": "ComfyUI", "images": ["8", 0]},
+                "class_type": "SaveImage"
+            },
+            "10": {
+                "inputs": {"filename_prefix": ["11", 0]},
+                "class_type": "SaveImage"
+            }
+        });
+        namespace.apply(&mut prompt);
+        assert_eq!(
+            prompt["9"]["inputs"]["filename_prefix"],
+            "tenant/job-1/ComfyUI"
+        );
+        assert_eq!(prompt["10"]["inputs"]["filename_prefix"], json!(["11", 0]));
+
+        let file = |subfolder: &str| FileInfo {
+            filename: "ComfyUI_00001_.png".to_string(),
+            subfolder: subfolder.to_string(),
+            r#type: "output".to_string(),
+        };
+        assert!(namespace.contains(&file("tenant/job-1")));
+        assert!(namespace.contains(&file("tenant/job-1/nested")));
+        assert!(!namespace.contains(&file("tenant/job-10")));
+        assert!(!namespace.contains(&file("")));
+        assert!(namespace.contains(&file("tenant\\job-1\\nested")));
+        assert!(!namespace.contains(&file("tenant\\job-10")));
+
+        for empty in ["", "/", "\\"] {
+            let namespace = OutputNamespace::new(empty);
+            let mut unchanged = prompt.clone();
+            namespace.apply(&mut unchanged);
+            assert_eq!(unchanged, prompt);
+            assert!(namespace.contains(&file("")));
+        }
+    }
+
+    /// Tests resolving model names against the available ones.
+    #[test]
+    fn test_model_match() {
+        let available = [
+            "flux1-dev-fp8.safetensors",
+            "sdxl/juggernautXL_v9.safetensors",
+            "v1-5-pruned-emaonly.ckpt",
+        ];
+
+        let found = ModelMatch::find("flux1-dev-fp8.safetensors", available, 0.9).unwrap();
+        assert_eq!(found.confidence, 1.0);
+
+        let found = ModelMatch::find("FLUX1-dev-FP8.safetensors", available, 0.9).unwrap();
+        assert_eq!(found.name, "flux1-dev-fp8.safetensors");
+
+        let found = ModelMatch::find("juggernautXL_v9.safetensors", available, 0.8).unwrap();
+        assert_eq!(found.name, "sdxl/juggernautXL_v9.safetensors");
+
+        let found = ModelMatch::find("flux1-dev.safetensors", available, 0.7).unwrap();
+        assert_eq!(found.name, "flux1-dev-fp8.safetensors");
+
+        assert!(ModelMatch::find("control_v11p_sd15_canny.pth", available, 0.7).is_none());
+    }
+
+    /// Tests lenient deserialization of numbers in event payloads.
+    #[test]
+    fn test_deserialize_lenient_numbers() {
+        let progress = json!({
+            "type": "progress",
+            "data": {"value": 2.6, "max": "20"}
+        });
+        let ev = ComfyEvent::from_json(&progress.to_string(), DecodeOptions::default()).unwrap();
+        assert!(matches!(ev, ComfyEvent::Unknown(_)));
+
+        let decode = |value: Value| {
+            let options = DecodeOptions {
+                strict: true,
+                lenient_numbers: true,
+                ..Default::default()
+            };
+            ComfyEvent::from_json(&value.to_string(), options)
+        };
+        let ev = decode(progress).unwrap();
+        let ComfyEvent::Progress { data } = ev else {
+            panic!("unexpected event: {ev:?}");
+        };
+        assert_eq!(data.value, 3);
+        assert_eq!(data.max, 20);
+
+        let ev = decode(json!({
+            "type": "execution_start",
+            "data": {"prompt_id": "xxxxxx", "timestamp": -1}
+        }))
+        .unwrap();
+        let ComfyEvent::ExecutionStart { data } = ev else {
+            panic!("unexpected event: {ev:?}");
+        };
+        assert_eq!(data.timestamp, 0);
+
+        let ev = decode(json!({
+            "type": "progress",
+            "data": {"value": [], "max": 20}
+        }));
+        assert!(ev.is_err());
+
+        // Signed integers are never rounded.
+        let queue_item = |number| QueueItem::try_from(vec![number, json!("xxxxxx"), json!({})]);
+        assert_eq!(queue_item(json!(-1.0)).unwrap().number, -1);
+        assert!(queue_item(json!(2.5)).is_err());
+    }
+
+    /// Tests deserialization of the queue.
+    #[test]
+    fn test_deserialize_queue() {
+        let queue = serde_json::from_value::<Queue>(json!({
+            "queue_running": [
+                [3, "running-id", {"9": {}}, {"client_id": "xxxxxx"}, ["9"]]
+            ],
+            "queue_pending": [
+                [4, "pending-id", {"9": {}}, {"client_id": "xxxxxx"}, ["9"], {}]
+            ]
+        }))
+        .unwrap();
+
+        assert!(queue.is_running("running-id"));
+        assert!(queue.is_pending("pending-id"));
+        assert!(!queue.is_running("pending-id"));
+        assert_eq!(queue.queue_pending[0].number, 4);
+        assert_eq!(queue.queue_pending[0].outputs_to_execute, ["9"]);
+
+        let value = serde_json::to_value(&queue.queue_running[0]).unwrap();
+        assert_eq!(
+            value,
+            json!([3, "running-id", {"9": {}}, {"client_id": "xxxxxx"}, ["9"]])
+        );
+
+        assert!(serde_json::from_value::<QueueItem>(json!([3])).is_err());
+
+        let item =
+            serde_json::from_value::<QueueItem>(json!([-2.0, "front-id", {"9": {}}])).unwrap();
+        assert_eq!(item.number, -2);
+    }
+
+    /// Tests the positions of prompts in the queue.
+    #[test]
+    fn test_queue_position() {
+        let queue = serde_json::from_value::<Queue>(json!({
+            "queue_running": [[3, "running-id", {}]],
+            "queue_pending": [
+                [6, "last-id", {}],
+                [-1, "front-id", {}],
+                [4, "next-id", {}]
+            ]
+        }))
+        .unwrap();
+
+        assert_eq!(queue.position("running-id"), Some(0));
+        assert_eq!(queue.position("front-id"), Some(1));
+        assert_eq!(queue.position("next-id"), Some(2));
+        assert_eq!(queue.position("last-id"), Some(3));
+        assert_eq!(queue.position("not-exists"), None);
+    }
+
+    /// Tests deserialization of the status of prompts inserted at the front of
+    /// the queue.
+    #[test]
+    fn test_deserialize_prompt_status() {
+        let status = serde_json::from_value::<PromptStatus>(json!({
+            "prompt_id": "xxxxxx",
+            "number": -3.0,
+            "node_errors": {}
+        }))
+        .unwrap();
+        assert_eq!(status.number, -3);
+    }
+
+    /// Tests deserialization of system stats.
+    #[test]
+    fn test_deserialize_system_stats() {
+        let stats = serde_json::from_value::<SystemStats>(json!({
+            "system": {
+                "os": "posix",
+                "ram_total": 67108864000u64,
+                "ram_free": 33554432000u64,
+                "comfyui_version": "0.3.30",
+                "python_version": "3.12.3 (main, Apr  9 2025, 08:09:14) [GCC 13.3.0]",
+                "pytorch_version": "2.6.0+cu126",
+                "embedded_python": false,
+                "argv": ["main.py", "--listen"]
+            },
+            "devices": [{
+                "name": "cuda:0 NVIDIA GeForce RTX 4090 : cudaMallocAsync",
+                "type": "cuda",
+                "index": 0,
+                "vram_total": 25393692672u64,
+                "vram_free": 24000000000u64,
+                "torch_vram_total": 0,
+                "torch_vram_free": 0
+            }]
+        }))
+        .unwrap();
+
+        assert_eq!(stats.system.comfyui_version.as_deref(), Some("0.3.30"));
+        assert_eq!(stats.devices[0].r#type, "cuda");
+        assert_eq!(stats.devices[0].vram_total, 25393692672);
+    }
+
+    /// Tests deserialization of server feature flags.
+    #[test]
+    fn test_deserialize_features() {
+        let features = serde_json::from_value::<Features>(json!({
+            "supports_preview_metadata": true,
+            "max_upload_size": 104857600,
+            "extension": {"manager": {"supports_v4": true}}
+        }))
+        .unwrap();
+        assert!(features.supports_preview_metadata);
+        assert_eq!(features.max_upload_size, Some(104857600));
+        assert_eq!(features.others["extension"]["manager"]["supports_v4"], true);
+
+        let features = serde_json::from_value::<Features>(json!({})).unwrap();
+        assert!(!features.supports_preview_metadata);
+        assert_eq!(features.max_upload_size, None);
+    }
+
+    /// Tests deserialization of raw server logs.
+    #[test]
+    fn test_deserialize_raw_logs() {
+        let logs = serde_json::from_value::<RawLogs>(json!({
+            "entries": [{"t": "2025-03-01T12:00:00.000000", "m": "Starting server\n"}],
+            "size": {"cols": 120, "rows": null}
+        }))
+        .unwrap();
+        assert_eq!(
+            logs.entries,
+            [LogEntry {
+                timestamp: "2025-03-01T12:00:00.000000".to_string(),
+                message: "Starting server\n".to_string(),
+            }]
+        );
+        assert_eq!(
+            logs.size,
+            TerminalSize {
+                cols: Some(120),
+                rows: None
+            }
+        );
+    }
+
+    /// Tests serialization of prompt options embedding the UI workflow.
+    #[test]
+    fn test_serialize_prompt_options() {
+        let options = PromptOptions::default();
+        assert_eq!(
+            serde_json::to_value(&options).unwrap(),
+            json!({"front": false})
+        );
+
+        let options = PromptOptions {
+            partial_execution_targets: Some(vec!["9".to_string()]),
+            ..Default::default()
+        };
+        assert_eq!(
+            serde_json::to_value(&options).unwrap(),
+            json!({"front": false, "partial_execution_targets": ["9"]})
+        );
+
+        let options = PromptOptions {
+            prompt_id: Some("xxxxxx".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            serde_json::to_value(&options).unwrap(),
+            json!({"front": false, "prompt_id": "xxxxxx"})
+        );
+
+        let options = PromptOptions {
+            extra_data: Some(json!({"extra_pnginfo": {"author": "me"}})),
+            ..Default::default()
+        }
+        .with_workflow(json!({"nodes": []}));
+        assert_eq!(
+            serde_json::to_value(&options).unwrap(),
+            json!({
+                "front": false,
+                "extra_data": {"extra_pnginfo": {"author": "me", "workflow": {"nodes": []}}}
+            })
+        );
+
+        for extra_data in [json!(null), json!([1]), json!({"extra_pnginfo": "png"})] {
+            let options = PromptOptions {
+                extra_data: Some(extra_data),
+                ..Default::default()
+            }
+            .with_workflow(json!({"nodes": []}));
+            assert_eq!(
+                options.extra_data.unwrap(),
+                json!({"extra_pnginfo": {"workflow": {"nodes": []}}})
+            );
+        }
+    }
+
+    /// Tests the query parameters of view options.
+    #[test]
+    fn test_view_options_query_pairs() {
+        assert!(ViewOptions::default().query_pairs().is_empty());
+
+        let options = ViewOptions {
+            preview_format: Some("webp".to_string()),
+            quality: Some(50),
+            channel: Some("rgb".to_string()),
+        };
+        assert_eq!(
+            options.query_pairs(),
+            [
+                ("preview", "webp;50".to_string()),
+                ("channel", "rgb".to_string())
+            ]
+        );
+
+        let options = ViewOptions {
+            preview_format: Some("jpeg".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(options.query_pairs(), [("preview", "jpeg".to_string())]);
+
+        let options = ViewOptions {
+            preview_format: Some("webp".to_string()),
+            quality: Some(255),
+            ..Default::default()
+        };
+        assert_eq!(options.query_pairs(), [("preview", "webp;100".to_string())]);
+    }
+
+    /// Tests deserialization of map entries preserving their order.
+    #[test]
+    fn test_deserialize_entries() {
+        let Entries(entries) =
+            serde_json::from_str::<Entries<u32>>(r#"{"b": 1, "a": 2, "c": 3}"#).unwrap();
+        assert_eq!(
+            entries,
+            [
+                ("b".to_string(), 1),
+                ("a".to_string(), 2),
+                ("c".to_string(), 3)
+            ]
+        );
+    }
+
+    /// Tests deserialization of node schemas.
+    #[test]
+    fn test_deserialize_object_info() {
+        let object_info = serde_json::from_value::<ObjectInfo>(json!({
+            "KSampler": {
+                "input": {
+                    "required": {
+                        "model": ["MODEL", {"tooltip": "The model."}],
+                        "seed": ["INT", {"default": 0, "min": 0, "max": 18446744073709551615u64}],
+                        "sampler_name": [["euler", "dpmpp_2m"], {"tooltip": "The sampler."}],
+                        "scheduler": ["COMBO", {"options": ["simple", "karras"]}]
+                    },
+                    "optional": {
+                        "denoise": ["FLOAT", {"default": 1.0, "min": "0.0", "step": 0.01}]
+                    }
+                },
+                "input_order": {"required": ["model", "seed", "sampler_name", "scheduler"]},
+                "output": ["LATENT"],
+                "output_is_list": [false],
+                "output_name": ["LATENT"],
+                "name": "KSampler",
+                "display_name": "KSampler",
+                "description": "Uses the provided model to denoise the latent image.",
+                "python_module": "nodes",
+                "category": "sampling",
+                "output_node": false
+            },
+            "CustomNode": {
+                "input": {"required": {"anything": "*"}},
+                "output": [["a", "b"]]
+            }
+        }))
+        .unwrap();
+
+        let ksampler = &object_info["KSampler"];
+        let seed = ksampler.input.get("seed").unwrap();
+        assert_eq!(seed.r#type, InputType::Named("INT".to_string()));
+        assert_eq!(seed.options.default, Some(json!(0)));
+        assert_eq!(seed.options.max, Some(u64::MAX as f64));
+        let sampler_name = ksampler.input.get("sampler_name").unwrap();
+        assert_eq!(sampler_name.combo_values().unwrap().len(), 2);
+        assert_eq!(
+            sampler_name.options.others["tooltip"],
+            json!("The sampler.")
+        );
+        let scheduler = ksampler.input.get("scheduler").unwrap();
+        assert_eq!(scheduler.combo_values().unwrap()[1], "karras");
+        let denoise = ksampler.input.get("denoise").unwrap();
+        assert_eq!(denoise.options.min, Some(0.0));
+        assert!(!ksampler.output_node);
+
+        let custom = &object_info["CustomNode"];
+        assert_eq!(
+            custom.input.get("anything").unwrap().r#type,
+            InputType::Named("*".to_string())
+        );
+        assert_eq!(custom.output, [r#"["a","b"]"#]);
+
+        let value = serde_json::to_value(seed).unwrap();
+        assert_eq!(
+            value,
+            json!(["INT", {"default": 0, "min": 0.0, "max": u64::MAX as f64}])
+        );
+    }
 }
@@ -0,0 +1,366 @@
+use crate::{
+    ClientError, ClientResult, ComfyUIClient, EventStream,
+    meta::{FileInfo, History, Prompt, WorkflowOutputs},
+};
+use serde_json::{Map, Value};
+
+/// A pipeline chaining multiple workflows, where the outputs of one stage are
+/// fed as inputs into the next one.
+///
+/// This enables multi-stage pipelines such as generate → upscale →
+/// interpolate without manually waiting for each prompt, collecting its
+/// outputs and wiring them into the next workflow.
+///
+/// # Example
+///
+/// ```no_run
+/// # async fn run(generate: &str, upscale: &str) -> comfyui_client::ClientResult<()> {
+/// use comfyui_client::{
+///     ClientBuilder,
+///     pipeline::{Binding, Pipeline, Stage},
+/// };
+///
+/// let (client, mut stream) = ClientBuilder::new("http://localhost:8188").build().await?;
+///
+/// let outputs = Pipeline::new()
+///     .stage(Stage::new(generate)?)
+///     .stage(Stage::new(upscale)?.bind(Binding::upload("9", "10", "image")))
+///     .run(&client, &mut stream)
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Pipeline {
+    stages: Vec<Stage>,
+}
+
+impl Pipeline {
+    /// Creates a new, empty [`Pipeline`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends a stage to the pipeline.
+    ///
+    /// # Parameters
+    ///
+    /// - `stage`: The [`Stage`] to run after the previously added ones.
+    ///
+    /// # Returns
+    ///
+    /// The updated [`Pipeline`] instance.
+    pub fn stage(mut self, stage: Stage) -> Self {
+        self.stages.push(stage);
+        self
+    }
+
+    /// Runs the stages of the pipeline one after another.
+    ///
+    /// Before a stage is sent, its bindings are resolved against the history
+    /// of the previous stage. The stage is then awaited by consuming events
+    /// from `stream` until its execution terminates.
+    ///
+    /// The first stage must not have bindings, as there is no previous stage
+    /// to resolve them against.
+    ///
+    /// # Parameters
+    ///
+    /// - `client`: The [`ComfyUIClient`] used to send the prompts.
+    /// - `stream`: The [`EventStream`] of the same client.
+    ///
+    /// # Returns
+    ///
+    /// The [`WorkflowOutputs`] of every stage on success, like
+    /// [`ComfyUIClient::execute`] returns them, or the first error
+    /// encountered, such as a failed or interrupted execution, or a
+    /// [`ClientError::InvalidBinding`] if the first stage has bindings.
+    pub async fn run(
+        &self, client: &ComfyUIClient, stream: &mut EventStream,
+    ) -> ClientResult<Vec<WorkflowOutputs>> {
+        if self
+            .stages
+            .first()
+            .is_some_and(|stage| !stage.bindings.is_empty())
+        {
+            return Err(ClientError::InvalidBinding(
+                "the first stage has no previous stage to bind".to_string(),
+            ));
+        }
+        let mut outputs = Vec::<WorkflowOutputs>::with_capacity(self.stages.len());
+        for stage in &self.stages {
+            let previous = outputs.last().map(|outputs| &outputs.history);
+            let prompt = stage.bound_prompt(client, previous).await?;
+            let status = client.post_prompt(&prompt).await?;
+            outputs.push(client.wait_for_prompt(stream, &status.prompt_id).await?);
+        }
+        Ok(outputs)
+    }
+}
+
+/// A single workflow of a [`Pipeline`], along with the bindings feeding the
+/// outputs of the previous stage into it.
+#[derive(Clone, Debug)]
+pub struct Stage {
+    prompt: Value,
+    bindings: Vec<Binding>,
+}
+
+impl Stage {
+    /// Creates a new [`Stage`] from a workflow in API format.
+    ///
+    /// # Parameters
+    ///
+    /// - `prompt`: representing the prompt data.
+    ///
+    /// # Returns
+    ///
+    /// A new [`Stage`] on success, or an error if the prompt isn't valid JSON.
+    pub fn new<'a>(prompt: impl Into<Prompt<'a>>) -> ClientResult<Self> {
+        let prompt = match prompt.into() {
+            Prompt::Str(prompt) => serde_json::from_str(prompt)?,
+            Prompt::Value(prompt) => prompt.clone(),
+        };
+        Ok(Self {
+            prompt,
+            bindings: Vec::new(),
+        })
+    }
+
+    /// Adds a binding from an output of the previous stage to an input of
+    /// this stage.
+    ///
+    /// A pipeline whose first stage has bindings fails to run.
+    ///
+    /// # Parameters
+    ///
+    /// - `binding`: The [`Binding`] to add.
+    ///
+    /// # Returns
+    ///
+    /// The updated [`Stage`] instance.
+    pub fn bind(mut self, binding: Binding) -> Self {
+        self.bindings.push(binding);
+        self
+    }
+
+    /// Returns the prompt of the stage with its bindings resolved against
+    /// the history of the previous stage, if any.
+    async fn bound_prompt(
+        &self, client: &ComfyUIClient, previous: Option<&History>,
+    ) -> ClientResult<Value> {
+        let mut prompt = self.prompt.clone();
+        if let Some(previous) = previous {
+            for binding in &self.bindings {
+                binding.apply(client, previous, &mut prompt).await?;
+            }
+        }
+        Ok(prompt)
+    }
+}
+
+/// Binds the first file output of a node of the previous stage to an input of
+/// a node of the next stage.
+#[derive(Clone, Debug)]
+pub struct Binding {
+    /// The identifier of the node producing the file in the previous stage.
+    pub output_node: String,
+    /// The identifier of the node consuming the file in the next stage.
+    pub input_node: String,
+    /// The name of the input receiving the file, e.g. `image`.
+    pub input: String,
+    /// How the file is handed over to the next stage.
+    pub mode: BindingMode,
+}
+
+impl Binding {
+    /// Creates a [`Binding`] which uploads the file as an input file.
+    pub fn upload(output_node: &str, input_node: &str, input: &str) -> Self {
+        Self::new(output_node, input_node, input, BindingMode::Upload)
+    }
+
+    /// Creates a [`Binding`] which references the file where it is stored.
+    pub fn reference(output_node: &str, input_node: &str, input: &str) -> Self {
+        Self::new(output_node, input_node, input, BindingMode::Reference)
+    }
+
+    fn new(output_node: &str, input_node: &str, input: &str, mode: BindingMode) -> Self {
+        Self {
+            output_node: output_node.to_string(),
+            input_node: input_node.to_string(),
+            input: input.to_string(),
+            mode,
+        }
+    }
+
+    /// Sets the input of the prompt to the file bound from the history.
+    ///
+    /// Uploaded files never overwrite existing input files, the name
+    /// returned by the server is bound instead.
+    ///
+    /// Fails with a [`ClientError::NodeNotFound`] if the prompt has no such
+    /// input node, like
+    /// [`Workflow::set_input`](crate::workflow::Workflow::set_input).
+    async fn apply(
+        &self, client: &ComfyUIClient, history: &History, prompt: &mut Value,
+    ) -> ClientResult<()> {
+        let file = history
+            .output(&self.output_node)
+            .and_then(|output| output.all_files().next())
+            .ok_or_else(|| ClientError::MissingOutput(self.output_node.clone()))?;
+
+        let value = match self.mode {
+            BindingMode::Upload => {
+                let data = client.get_view(file).await?;
+                let info = FileInfo {
+                    filename: file.filename.clone(),
+                    subfolder: file.subfolder.clone(),
+                    r#type: "input".to_string(),
+                };
+                let info = client.upload_image(data, &info, false).await?;
+                input_file_path(&info)
+            }
+            BindingMode::Reference => format!("{} [{}]", input_file_path(file), file.r#type),
+        };
+
+        let Some(Value::Object(node)) = prompt
+            .as_object_mut()
+            .and_then(|nodes| nodes.get_mut(&self.input_node))
+        else {
+            return Err(ClientError::NodeNotFound(self.input_node.clone()));
+        };
+        let inputs = node
+            .entry("inputs")
+            .or_insert_with(|| Value::Object(Map::new()));
+        if !inputs.is_object() {
+            *inputs = Value::Object(Map::new());
+        }
+        inputs[&self.input] = Value::String(value);
+        Ok(())
+    }
+}
+
+/// How a file is handed over from one stage to the next.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BindingMode {
+    /// Downloads the file and uploads it again as an input file, renamed by
+    /// the server if an input file of the same name exists.
+    Upload,
+    /// References the file in place, using ComfyUI's annotated file path
+    /// syntax (e.g. `ComfyUI_00001_.png [output]`).
+    Reference,
+}
+
+/// Returns the path of a file relative to its base directory, as expected by
+/// file inputs such as the `image` input of `LoadImage`.
+pub(crate) fn input_file_path(file: &FileInfo) -> String {
+    if file.subfolder.is_empty() {
+        file.filename.clone()
+    } else {
+        format!("{}/{}", file.subfolder, file.filename)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ClientBuilder, tests::serve_json};
+    use serde_json::json;
+
+    async fn client() -> ComfyUIClient {
+        ClientBuilder::new("http://127.0.0.1:8188/")
+            .build_only_http()
+            .await
+            .unwrap()
+    }
+
+    fn history() -> History {
+        serde_json::from_value(json!({
+            "outputs": {
+                "9": {"images": [{"filename": "a.png", "subfolder": "run", "type": "output"}]}
+            }
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_binding_apply() {
+        let client = client().await;
+        let binding = Binding::reference("9", "10", "image");
+
+        let mut prompt = json!({"10": {"class_type": "LoadImage", "inputs": {"image": "x.png"}}});
+        binding
+            .apply(&client, &history(), &mut prompt)
+            .await
+            .unwrap();
+        assert_eq!(prompt["10"]["inputs"]["image"], "run/a.png [output]");
+
+        let mut prompt = json!({"10": {"class_type": "LoadImage", "inputs": null}});
+        binding
+            .apply(&client, &history(), &mut prompt)
+            .await
+            .unwrap();
+        assert_eq!(prompt["10"]["inputs"]["image"], "run/a.png [output]");
+
+        for mut prompt in [json!({}), json!({"10": "LoadImage"}), json!([])] {
+            let original = prompt.clone();
+            assert!(matches!(
+                binding.apply(&client, &history(), &mut prompt).await,
+                Err(ClientError::NodeNotFound(node)) if node == "10"
+            ));
+            assert_eq!(prompt, original);
+        }
+
+        let binding = Binding::reference("11", "10", "image");
+        assert!(matches!(
+            binding.apply(&client, &history(), &mut json!({})).await,
+            Err(ClientError::MissingOutput(node)) if node == "11"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_binding_apply_upload() {
+        let (base_url, mut requests) = serve_json(vec![
+            json!("image"),
+            json!({"name": "a (1).png", "subfolder": "run", "type": "input"}),
+        ])
+        .await;
+        let client = ClientBuilder::new(base_url.as_str())
+            .build_only_http()
+            .await
+            .unwrap();
+
+        let mut prompt = json!({"10": {"class_type": "LoadImage", "inputs": {}}});
+        Binding::upload("9", "10", "image")
+            .apply(&client, &history(), &mut prompt)
+            .await
+            .unwrap();
+        // The name returned by the server is bound.
+        assert_eq!(prompt["10"]["inputs"]["image"], "run/a (1).png");
+        assert!(requests.recv().await.unwrap().starts_with("GET /view?"));
+        let request = requests.recv().await.unwrap();
+        assert!(request.starts_with("POST /upload/image "));
+        assert!(request.contains("name=\"overwrite\"\r\n\r\nfalse\r\n"));
+    }
+
+    #[tokio::test]
+    async fn test_stage_chaining() {
+        let client = client().await;
+        let stage = Stage::new(&json!({
+            "10": {"class_type": "LoadImage", "inputs": {"image": "x.png"}}
+        }))
+        .unwrap()
+        .bind(Binding::reference("9", "10", "image"));
+
+        let prompt = stage.bound_prompt(&client, None).await.unwrap();
+        assert_eq!(prompt["10"]["inputs"]["image"], "x.png");
+        let prompt = stage.bound_prompt(&client, Some(&history())).await.unwrap();
+        assert_eq!(prompt["10"]["inputs"]["image"], "run/a.png [output]");
+
+        let (tx, rx) = tokio::sync::mpsc::channel(1);
+        let mut stream = EventStream::from_receiver(rx);
+        let result = Pipeline::new().stage(stage).run(&client, &mut stream).await;
+        assert!(matches!(result, Err(ClientError::InvalidBinding(_))));
+        drop(tx);
+    }
+}
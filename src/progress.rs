@@ -0,0 +1,265 @@
+use crate::{
+    ClientError, ClientResult, ComfyUIClient, EventStream,
+    errors::CancellationReason,
+    meta::{ComfyEvent, Event},
+};
+use futures_util::StreamExt;
+use indicatif::{ProgressBar, ProgressStyle};
+
+/// The default template of the progress bar, showing the executing node, the
+/// progress of its steps and the estimated remaining time.
+const DEFAULT_TEMPLATE: &str =
+    "{spinner} [{elapsed_precise}] {bar:40} {pos}/{len} {msg} (ETA {eta})";
+
+/// Drives an [`indicatif::ProgressBar`] from the events of a prompt.
+///
+/// While the prompt is pending, the bar shows its position in the queue. Once
+/// it executes, the bar follows the step progress of the
+/// executing node, and finishes when the execution terminates.
+///
+/// Bars added to an [`indicatif::MultiProgress`] can be used via
+/// [`with_bar`](Self::with_bar) to present several prompts at once.
+///
+/// # Example
+///
+/// ```no_run
+/// # async fn run(prompt: &str) -> comfyui_client::ClientResult<()> {
+/// use comfyui_client::{ClientBuilder, progress::PromptProgressBar};
+///
+/// let (client, mut stream) = ClientBuilder::new("http://localhost:8188").build().await?;
+/// let status = client.post_prompt(prompt).await?;
+/// PromptProgressBar::new(&status.prompt_id)
+///     .track(&client, &mut stream)
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct PromptProgressBar {
+    bar: ProgressBar,
+    prompt_id: String,
+    executing: bool,
+}
+
+impl PromptProgressBar {
+    /// Creates a new [`PromptProgressBar`] drawing a default styled bar.
+    ///
+    /// # Parameters
+    ///
+    /// - `prompt_id`: The ID of the prompt to track.
+    pub fn new(prompt_id: &str) -> Self {
+        let bar = ProgressBar::new(0);
+        if let Ok(style) = ProgressStyle::with_template(DEFAULT_TEMPLATE) {
+            bar.set_style(style);
+        }
+        Self::with_bar(bar, prompt_id)
+    }
+
+    /// Creates a new [`PromptProgressBar`] driving the given bar.
+    ///
+    /// # Parameters
+    ///
+    /// - `bar`: The [`ProgressBar`] to drive, e.g. one added to a
+    ///   [`indicatif::MultiProgress`].
+    /// - `prompt_id`: The ID of the prompt to track.
+    pub fn with_bar(bar: ProgressBar, prompt_id: &str) -> Self {
+        bar.set_message("queued");
+        Self {
+            bar,
+            prompt_id: prompt_id.to_string(),
+            executing: false,
+        }
+    }
+
+    /// Returns the driven progress bar.
+    pub fn bar(&self) -> &ProgressBar {
+        &self.bar
+    }
+
+    /// Shows the position of the pending prompt in the queue, see
+    /// [`Queue::position`](crate::meta::Queue::position).
+    ///
+    /// # Parameters
+    ///
+    /// - `position`: The number of prompts executing before it.
+    pub fn set_queue_position(&self, position: usize) {
+        if !self.executing {
+            self.bar.set_message(format!("queued, {position} ahead"));
+        }
+    }
+
+    /// Updates the progress bar according to an event of the prompt.
+    ///
+    /// Events of other prompts are ignored. The queue position isn't derived
+    /// from `status` events, as they only report the length of the whole
+    /// queue, see [`set_queue_position`](Self::set_queue_position).
+    ///
+    /// # Parameters
+    ///
+    /// - `ev`: An [`Event`] received from the [`EventStream`].
+    ///
+    /// # Returns
+    ///
+    /// `true` if the execution of the prompt has terminated, `false`
+    /// otherwise.
+    pub fn handle_event(&mut self, ev: &Event) -> bool {
+        let Event::Comfy(ev) = ev else {
+            return false;
+        };
+        match ev {
+            ComfyEvent::ExecutionStart { data } if data.prompt_id == self.prompt_id => {
+                self.executing = true;
+                self.bar.set_message("started");
+            }
+            ComfyEvent::Executing { data } if data.prompt_id == self.prompt_id => {
+                self.executing = true;
+                if let Some(node) = data.display_node.as_ref().or(data.node.as_ref()) {
+                    self.bar.set_message(format!("node {node}"));
+                }
+            }
+            // Older servers don't report the prompt of progress events.
+            ComfyEvent::Progress { data }
+                if self.executing
+                    && data
+                        .prompt_id
+                        .as_ref()
+                        .is_none_or(|prompt_id| *prompt_id == self.prompt_id) =>
+            {
+                self.bar.set_length(data.max as u64);
+                self.bar.set_position(data.value as u64);
+            }
+            ComfyEvent::ExecutionSuccess { data } if data.prompt_id == self.prompt_id => {
+                self.bar.finish_with_message("done");
+                return true;
+            }
+            ComfyEvent::ExecutionError { data } if data.prompt_id == self.prompt_id => {
+                self.bar
+                    .abandon_with_message(format!("failed: {}", data.exception_message));
+                return true;
+            }
+            ComfyEvent::ExecutionInterrupted { data } if data.prompt_id == self.prompt_id => {
+                self.bar.abandon_with_message("interrupted");
+                return true;
+            }
+            _ => {}
+        }
+        false
+    }
+
+    /// Consumes events from the stream and updates the progress bar until the
+    /// execution of the prompt terminates.
+    ///
+    /// While the prompt is pending, its queue position is fetched via
+    /// `client` whenever the queue changes.
+    ///
+    /// # Parameters
+    ///
+    /// - `client`: The client the prompt was sent with.
+    /// - `stream`: The [`EventStream`] of the same client.
+    ///
+    /// # Returns
+    ///
+    /// `()` once the execution terminated, a [`ClientError::Cancelled`] with
+    /// [`CancellationReason::ConnectionLost`] if the stream ended before, or
+    /// the first error received from the stream.
+    pub async fn track(
+        mut self, client: &ComfyUIClient, stream: &mut EventStream,
+    ) -> ClientResult<()> {
+        while let Some(ev) = stream.next().await {
+            let ev = ev?;
+            if self.handle_event(&ev) {
+                return Ok(());
+            }
+            if let Event::Comfy(ComfyEvent::Status { .. }) = ev {
+                if let Some(position) = client.queue_position(&self.prompt_id).await? {
+                    self.set_queue_position(position);
+                }
+            }
+        }
+        self.bar.abandon_with_message("connection lost");
+        Err(ClientError::Cancelled(CancellationReason::ConnectionLost))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ClientBuilder, tests::serve_json};
+    use serde_json::{Value, json};
+    use tokio::sync::mpsc;
+
+    fn event(value: Value) -> Event {
+        Event::Comfy(serde_json::from_value(value).unwrap())
+    }
+
+    #[test]
+    fn test_handle_event() {
+        let mut progress = PromptProgressBar::with_bar(ProgressBar::hidden(), "p");
+        assert!(!progress.handle_event(&event(json!({
+            "type": "progress",
+            "data": {"value": 5, "max": 20, "prompt_id": "p", "node": "3"}
+        }))));
+        // Progress is only shown once the prompt executes.
+        assert_eq!(progress.bar().position(), 0);
+
+        assert!(!progress.handle_event(&event(json!({
+            "type": "execution_start",
+            "data": {"prompt_id": "p", "timestamp": 0}
+        }))));
+        assert!(!progress.handle_event(&event(json!({
+            "type": "progress",
+            "data": {"value": 5, "max": 20, "prompt_id": "p", "node": "3"}
+        }))));
+        assert!(!progress.handle_event(&event(json!({
+            "type": "progress",
+            "data": {"value": 1, "max": 10, "prompt_id": "other", "node": "3"}
+        }))));
+        assert_eq!(progress.bar().position(), 5);
+        assert_eq!(progress.bar().length(), Some(20));
+
+        assert!(!progress.handle_event(&event(json!({
+            "type": "execution_success",
+            "data": {"prompt_id": "other", "timestamp": 1}
+        }))));
+        assert!(progress.handle_event(&event(json!({
+            "type": "execution_success",
+            "data": {"prompt_id": "p", "timestamp": 1}
+        }))));
+        assert_eq!(progress.bar().message(), "done");
+    }
+
+    #[tokio::test]
+    async fn test_track() {
+        let (base_url, _paths) = serve_json(vec![json!({
+            "queue_running": [[0, "other", {}, {}, []]],
+            "queue_pending": [[2, "p", {}, {}, []], [1, "next", {}, {}, []]]
+        })])
+        .await;
+        let client = ClientBuilder::new(base_url.as_str())
+            .build_only_http()
+            .await
+            .unwrap();
+
+        // With a single slot, sending an event completes once the previous
+        // one has been received, i.e. the one before has been handled.
+        let (tx, rx) = mpsc::channel(1);
+        let mut stream = EventStream::from_receiver(rx);
+        let bar = ProgressBar::hidden();
+        let progress = PromptProgressBar::with_bar(bar.clone(), "p");
+        let track = tokio::spawn(async move { progress.track(&client, &mut stream).await });
+        for value in [
+            json!({"type": "status", "data": {"status": {"exec_info": {"queue_remaining": 3}}}}),
+            json!({"type": "execution_start", "data": {"prompt_id": "other", "timestamp": 0}}),
+            json!({"type": "execution_success", "data": {"prompt_id": "other", "timestamp": 1}}),
+        ] {
+            tx.send(Ok(event(value))).await.unwrap();
+        }
+        assert_eq!(bar.message(), "queued, 2 ahead");
+
+        drop(tx);
+        assert!(matches!(
+            track.await.unwrap(),
+            Err(ClientError::Cancelled(CancellationReason::ConnectionLost))
+        ));
+        assert_eq!(bar.message(), "connection lost");
+    }
+}
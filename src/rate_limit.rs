@@ -0,0 +1,80 @@
+use std::{
+    num::NonZeroU32,
+    sync::{Arc, Mutex, PoisonError},
+};
+use tokio::time::{Duration, Instant, sleep};
+
+/// A token bucket limiting the rate of HTTP requests, shared by all clones of
+/// a client.
+///
+/// The bucket holds up to one second worth of requests, allowing short
+/// bursts. Requests exceeding the rate reserve a future token and wait for
+/// it, so they're sent in the order they arrived.
+#[derive(Clone, Debug)]
+pub(crate) struct RateLimiter {
+    requests_per_second: f64,
+    bucket: Arc<Mutex<Bucket>>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    /// The available tokens, negative if tokens are reserved.
+    tokens: f64,
+    updated_at: Instant,
+}
+
+impl RateLimiter {
+    /// Creates a new [`RateLimiter`] with a full bucket.
+    pub(crate) fn new(requests_per_second: NonZeroU32) -> Self {
+        let requests_per_second = f64::from(requests_per_second.get());
+        Self {
+            requests_per_second,
+            bucket: Arc::new(Mutex::new(Bucket {
+                tokens: requests_per_second,
+                updated_at: Instant::now(),
+            })),
+        }
+    }
+
+    /// Waits until a request may be sent.
+    ///
+    /// The token is consumed even if the returned future is dropped before
+    /// completion.
+    pub(crate) async fn acquire(&self) {
+        if let Some(delay) = self.reserve(Instant::now()) {
+            sleep(delay).await;
+        }
+    }
+
+    /// Takes a token, returning the delay until it's available if the bucket
+    /// is empty.
+    fn reserve(&self, now: Instant) -> Option<Duration> {
+        let mut bucket = self.bucket.lock().unwrap_or_else(PoisonError::into_inner);
+        let elapsed = now.saturating_duration_since(bucket.updated_at);
+        bucket.tokens = (bucket.tokens + elapsed.as_secs_f64() * self.requests_per_second)
+            .min(self.requests_per_second);
+        bucket.updated_at = now;
+        bucket.tokens -= 1.0;
+        (bucket.tokens < 0.0)
+            .then(|| Duration::from_secs_f64(-bucket.tokens / self.requests_per_second))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rate_limiter() {
+        let limiter = RateLimiter::new(NonZeroU32::new(2).unwrap());
+        let now = Instant::now();
+        assert_eq!(limiter.reserve(now), None);
+        assert_eq!(limiter.reserve(now), None);
+        assert_eq!(limiter.reserve(now), Some(Duration::from_millis(500)));
+        assert_eq!(limiter.reserve(now), Some(Duration::from_secs(1)));
+
+        // The reserved tokens are paid back before new ones accumulate.
+        let later = now + Duration::from_secs(1);
+        assert_eq!(limiter.reserve(later), Some(Duration::from_millis(500)));
+    }
+}
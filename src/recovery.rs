@@ -0,0 +1,315 @@
+use crate::{
+    ClientResult, ComfyUIClient,
+    meta::{ComfyEvent, compare_node_ids},
+};
+use log::{debug, warn};
+use reqwest::Method;
+use serde_json::{Value, json};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+};
+
+/// The prompts tracked for missed-event recovery, mapped to the nodes whose
+/// `executed` event was already received.
+type Prompts = HashMap<String, HashSet<String>>;
+
+/// Tracks the prompts which didn't terminate yet, so that their events missed
+/// while the websocket was disconnected can be recovered from the history.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct PromptTracker {
+    prompts: Arc<Mutex<Prompts>>,
+    sid: Arc<Mutex<Option<String>>>,
+}
+
+impl PromptTracker {
+    /// Creates a tracker tracking the given prompts, e.g. restored from a
+    /// [`SessionState`](crate::meta::SessionState).
+    pub(crate) fn with_prompts(prompt_ids: &[String]) -> Self {
+        let tracker = Self::default();
+        for prompt_id in prompt_ids {
+            tracker.track(prompt_id);
+        }
+        tracker
+    }
+
+    /// Returns the IDs of the tracked prompts, sorted.
+    pub(crate) fn prompt_ids(&self) -> Vec<String> {
+        let mut prompt_ids = self.lock().keys().cloned().collect::<Vec<_>>();
+        prompt_ids.sort_unstable();
+        prompt_ids
+    }
+
+    /// Returns the session ID last reported in a `status` event.
+    pub(crate) fn sid(&self) -> Option<String> {
+        self.sid
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Starts tracking a prompt, e.g. after sending it.
+    pub(crate) fn track(&self, prompt_id: &str) {
+        self.lock().entry(prompt_id.to_string()).or_default();
+    }
+
+    /// Stops tracking a prompt.
+    pub(crate) fn untrack(&self, prompt_id: &str) {
+        self.lock().remove(prompt_id);
+    }
+
+    /// Records an event, tracking its prompt until the execution terminates.
+    pub(crate) fn observe(&self, ev: &ComfyEvent) {
+        if let ComfyEvent::Status { sid: Some(sid), .. } = ev {
+            *self.sid.lock().unwrap_or_else(PoisonError::into_inner) = Some(sid.clone());
+        }
+        let Some(prompt_id) = ev.prompt_id() else {
+            return;
+        };
+        let mut prompts = self.lock();
+        match ev {
+            ComfyEvent::ExecutionSuccess { .. }
+            | ComfyEvent::ExecutionError { .. }
+            | ComfyEvent::ExecutionInterrupted { .. } => {
+                prompts.remove(prompt_id);
+            }
+            ComfyEvent::Executed { data } => {
+                prompts
+                    .entry(prompt_id.to_string())
+                    .or_default()
+                    .insert(data.node.clone());
+            }
+            _ => {
+                prompts.entry(prompt_id.to_string()).or_default();
+            }
+        }
+    }
+
+    /// Locks the prompts, ignoring poisoning as they stay consistent.
+    fn lock(&self) -> MutexGuard<'_, Prompts> {
+        self.prompts.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl ComfyUIClient {
+    /// Records an event received from the websocket for recovery, if enabled.
+    pub(crate) fn track_event(&self, ev: &ComfyEvent) {
+        if let Some(tracker) = &self.prompt_tracker {
+            tracker.observe(ev);
+        }
+    }
+
+    /// Recovers the events of the tracked prompts which terminated while the
+    /// websocket was disconnected, by reconciling them with their history.
+    ///
+    /// For each such prompt, an `executed` event is synthesized per node
+    /// whose event was missed, followed by the terminal event recorded in the
+    /// history. Prompts still queued or running stay tracked, as their events
+    /// keep being delivered. Prompts which are neither queued nor recorded in
+    /// the history, e.g. because they were deleted, are no longer tracked.
+    ///
+    /// # Returns
+    ///
+    /// The synthesized events, which are also recorded by the tracker.
+    pub(crate) async fn recover_missed_events(&self) -> Vec<ComfyEvent> {
+        let Some(tracker) = &self.prompt_tracker else {
+            return Vec::new();
+        };
+        let prompts = tracker.lock().clone();
+        if prompts.is_empty() {
+            return Vec::new();
+        }
+        // The queue is fetched before the histories, so that a prompt missing
+        // from both hasn't merely terminated in the meantime.
+        let queue = match self.get_queue().await {
+            Ok(queue) => Some(queue),
+            Err(err) => {
+                warn!(err:%; "failed to fetch the queue for recovery");
+                None
+            }
+        };
+
+        let mut events = Vec::new();
+        for (prompt_id, executed_nodes) in prompts {
+            let history = match self.get_raw_history(&prompt_id).await {
+                Ok(Some(history)) => history,
+                Ok(None) => {
+                    if queue.as_ref().is_some_and(|queue| {
+                        !queue.is_pending(&prompt_id) && !queue.is_running(&prompt_id)
+                    }) {
+                        debug!(prompt_id:%; "stopped tracking prompt missing from queue and history");
+                        tracker.untrack(&prompt_id);
+                    }
+                    continue;
+                }
+                Err(err) => {
+                    warn!(prompt_id:%, err:%; "failed to recover missed events of prompt");
+                    continue;
+                }
+            };
+            let recovered = recovered_events(&prompt_id, &executed_nodes, &history);
+            debug!(prompt_id:%, count = recovered.len(); "recovered missed events of prompt");
+            for ev in &recovered {
+                tracker.observe(ev);
+            }
+            events.extend(recovered);
+        }
+        events
+    }
+
+    /// Retrieves the history of a prompt without parsing it.
+    async fn get_raw_history(&self, prompt_id: &str) -> ClientResult<Option<Value>> {
+        let resp = self
+            .send(self.request(
+                Method::GET,
+                self.base_url.join(&format!("history/{prompt_id}"))?,
+            ))
+            .await
+            .map_err(|err| err.with_prompt_id(prompt_id))?;
+        let resp = Self::error_for_status(resp)
+            .await
+            .map_err(|err| err.with_prompt_id(prompt_id))?;
+        let mut histories = ComfyUIClient::json::<HashMap<String, Value>>(resp)
+            .await
+            .map_err(|err| err.with_prompt_id(prompt_id))?;
+        Ok(histories.remove(prompt_id))
+    }
+}
+
+/// Synthesizes the events of a terminated prompt from its history.
+///
+/// The terminal event is taken from the status messages of the history. If the
+/// server doesn't record them, `execution_success` is assumed for a completed
+/// prompt.
+fn recovered_events(
+    prompt_id: &str, executed_nodes: &HashSet<String>, history: &Value,
+) -> Vec<ComfyEvent> {
+    let mut events = Vec::new();
+
+    if let Some(outputs) = history["outputs"].as_object() {
+        let mut nodes = outputs
+            .keys()
+            .filter(|node| !executed_nodes.contains(*node))
+            .collect::<Vec<_>>();
+        nodes.sort_by(|a, b| compare_node_ids(a, b));
+        for node in nodes {
+            let value = json!({
+                "type": "executed",
+                "data": {"node": node, "prompt_id": prompt_id, "output": outputs[node]},
+            });
+            match serde_json::from_value(value) {
+                Ok(ev) => events.push(ev),
+                Err(err) => warn!(prompt_id, node:%, err:%; "failed to recover executed event"),
+            }
+        }
+    }
+
+    let terminal = history["status"]["messages"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|message| Some((message[0].as_str()?, &message[1])))
+        .rfind(|(event_type, _)| {
+            matches!(
+                *event_type,
+                "execution_success" | "execution_error" | "execution_interrupted"
+            )
+        })
+        .map(|(event_type, data)| json!({"type": event_type, "data": data}));
+    let terminal = match terminal {
+        Some(terminal) => terminal,
+        None if history["status"]["completed"].as_bool() != Some(false) => {
+            json!({"type": "execution_success", "data": {"prompt_id": prompt_id}})
+        }
+        None => return events,
+    };
+    match serde_json::from_value(terminal) {
+        Ok(ev) => events.push(ev),
+        Err(err) => warn!(prompt_id, err:%; "failed to recover terminal event"),
+    }
+
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ClientBuilder, tests::serve_json};
+
+    #[test]
+    fn test_prompt_tracker() {
+        let tracker = PromptTracker::default();
+        tracker.track("first");
+        for value in [
+            json!({"type": "execution_start", "data": {"prompt_id": "second", "timestamp": 0}}),
+            json!({"type": "executed", "data": {"node": "9", "prompt_id": "second", "output": null}}),
+            json!({"type": "execution_success", "data": {"prompt_id": "first"}}),
+        ] {
+            tracker.observe(&serde_json::from_value(value).unwrap());
+        }
+        let prompts = tracker.lock().clone();
+        assert_eq!(prompts.len(), 1);
+        assert_eq!(prompts["second"], HashSet::from(["9".to_string()]));
+        assert_eq!(tracker.sid(), None);
+
+        let tracker = PromptTracker::with_prompts(&["b".to_string(), "a".to_string()]);
+        let status = json!({
+            "type": "status",
+            "data": {"status": {"exec_info": {"queue_remaining": 0}}},
+            "sid": "session"
+        });
+        tracker.observe(&serde_json::from_value(status).unwrap());
+        assert_eq!(tracker.prompt_ids(), ["a", "b"]);
+        assert_eq!(tracker.sid().as_deref(), Some("session"));
+    }
+
+    #[test]
+    fn test_recovered_events() {
+        let history = json!({
+            "outputs": {"9": {"images": []}, "10": {"images": []}},
+            "status": {
+                "status_str": "success",
+                "completed": true,
+                "messages": [
+                    ["execution_start", {"prompt_id": "xxxxxx", "timestamp": 0}],
+                    ["execution_success", {"prompt_id": "xxxxxx", "timestamp": 1}],
+                ],
+            },
+        });
+        let events = recovered_events("xxxxxx", &HashSet::from(["9".to_string()]), &history);
+        assert_eq!(events.len(), 2);
+        assert!(matches!(&events[0], ComfyEvent::Executed { data } if data.node == "10"));
+        assert!(matches!(events[1], ComfyEvent::ExecutionSuccess { .. }));
+
+        // Older servers don't record the status messages.
+        let history = json!({"outputs": {}});
+        let events = recovered_events("xxxxxx", &HashSet::new(), &history);
+        assert_eq!(events.len(), 1);
+        assert!(matches!(events[0], ComfyEvent::ExecutionSuccess { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_recover_missing_prompts() {
+        let (base_url, _requests) = serve_json(vec![
+            json!({"queue_running": [], "queue_pending": [[1, "queued", {}, {}, []]]}),
+            json!({}),
+            json!({"queue_running": [], "queue_pending": []}),
+            json!({}),
+        ])
+        .await;
+        let mut client = ClientBuilder::new(base_url.as_str())
+            .build_only_http()
+            .await
+            .unwrap();
+        let tracker = PromptTracker::with_prompts(&["queued".to_string()]);
+        client.prompt_tracker = Some(tracker.clone());
+
+        // A queued prompt without history stays tracked.
+        assert!(client.recover_missed_events().await.is_empty());
+        assert_eq!(tracker.prompt_ids(), ["queued"]);
+
+        // Once it disappeared from the queue, it is no longer tracked.
+        assert!(client.recover_missed_events().await.is_empty());
+        assert!(tracker.prompt_ids().is_empty());
+    }
+}
@@ -0,0 +1,364 @@
+use crate::{ClientResult, errors::TemplateError, workflow::Workflow};
+use serde_json::{Map, Value};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+};
+
+/// A workflow in the API prompt format containing `{{placeholders}}`, which
+/// renders concrete workflows from parameter values.
+///
+/// A string value consisting of a single placeholder, e.g. `"{{seed}}"`, is
+/// replaced by the parameter value as is, so numbers stay numbers.
+/// Placeholders within longer strings, e.g. `"a photo of {{subject}}"`, are
+/// replaced by the text of the value. A placeholder may declare the
+/// [`ParamType`] of its values, e.g. `{{steps:int}}`, which is checked when
+/// rendering.
+///
+/// # Example
+///
+/// ```no_run
+/// # async fn run(json: &str) -> comfyui_client::ClientResult<()> {
+/// use comfyui_client::{ClientBuilder, template::PromptTemplate};
+/// use serde_json::json;
+/// use std::collections::HashMap;
+///
+/// let client = ClientBuilder::new("http://localhost:8188")
+///     .build_only_http()
+///     .await?;
+/// let template = PromptTemplate::from_json(json)?;
+/// for cfg in [4.0, 6.0, 8.0] {
+///     let params = HashMap::from([("cfg".to_string(), json!(cfg))]);
+///     client.post_prompt(&template.render(&params)?).await?;
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct PromptTemplate {
+    template: Value,
+    params: BTreeMap<String, Option<ParamType>>,
+}
+
+impl PromptTemplate {
+    /// Parses a template from the JSON of a workflow in the API prompt
+    /// format.
+    ///
+    /// # Parameters
+    ///
+    /// - `json`: The JSON of the workflow containing placeholders.
+    ///
+    /// # Returns
+    ///
+    /// The parsed [`PromptTemplate`], or an error if the JSON is invalid or a
+    /// placeholder declares an unknown or conflicting type.
+    pub fn from_json(json: &str) -> ClientResult<Self> {
+        Self::from_value(serde_json::from_str(json)?)
+    }
+
+    /// Creates a template from a workflow in the API prompt format.
+    ///
+    /// # Parameters
+    ///
+    /// - `template`: The workflow containing placeholders as JSON value.
+    ///
+    /// # Returns
+    ///
+    /// The [`PromptTemplate`], or an error if a placeholder declares an
+    /// unknown or conflicting type.
+    pub fn from_value(template: Value) -> ClientResult<Self> {
+        let mut params = BTreeMap::new();
+        collect_params(&template, &mut params)?;
+        Ok(Self { template, params })
+    }
+
+    /// Returns the parameters of the template along with their declared
+    /// type, if any, ordered by name.
+    pub fn params(&self) -> impl Iterator<Item = (&str, Option<ParamType>)> {
+        self.params
+            .iter()
+            .map(|(name, param_type)| (name.as_str(), *param_type))
+    }
+
+    /// Renders a workflow by substituting the placeholders with the given
+    /// parameter values.
+    ///
+    /// Values of parameters which don't occur in the template are ignored.
+    ///
+    /// # Parameters
+    ///
+    /// - `params`: The values of the parameters by name.
+    ///
+    /// # Returns
+    ///
+    /// The rendered [`Workflow`], or an error if a parameter is missing or
+    /// its value doesn't match the declared type.
+    pub fn render(&self, params: &HashMap<String, Value>) -> ClientResult<Workflow> {
+        for (name, param_type) in &self.params {
+            let value = params
+                .get(name)
+                .ok_or_else(|| TemplateError::MissingParameter(name.clone()))?;
+            if let Some(param_type) = param_type {
+                if !param_type.matches(value) {
+                    return Err(TemplateError::TypeMismatch {
+                        name: name.clone(),
+                        expected: *param_type,
+                        value: value.clone(),
+                    }
+                    .into());
+                }
+            }
+        }
+        Workflow::from_value(render_value(&self.template, params)?)
+    }
+}
+
+/// The type of the values of a template parameter, declared as
+/// `{{name:type}}`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ParamType {
+    /// An integer, declared as `int`.
+    Int,
+    /// A number, declared as `float`.
+    Float,
+    /// A string, declared as `string`.
+    String,
+    /// A boolean, declared as `bool`.
+    Bool,
+}
+
+impl ParamType {
+    /// Checks whether a value is of this type.
+    pub fn matches(self, value: &Value) -> bool {
+        match self {
+            ParamType::Int => value.is_i64() || value.is_u64(),
+            ParamType::Float => value.is_number(),
+            ParamType::String => value.is_string(),
+            ParamType::Bool => value.is_boolean(),
+        }
+    }
+
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "int" => Some(ParamType::Int),
+            "float" => Some(ParamType::Float),
+            "string" => Some(ParamType::String),
+            "bool" => Some(ParamType::Bool),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ParamType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ParamType::Int => "int",
+            ParamType::Float => "float",
+            ParamType::String => "string",
+            ParamType::Bool => "bool",
+        })
+    }
+}
+
+/// A placeholder found in a string of the template.
+struct Placeholder<'a> {
+    /// The byte range of the placeholder, including the braces.
+    start: usize,
+    end: usize,
+    name: &'a str,
+    param_type: Option<ParamType>,
+}
+
+/// Finds the placeholders in a string. Braces which don't enclose a valid
+/// parameter name are kept as text.
+fn placeholders(text: &str) -> Result<Vec<Placeholder<'_>>, TemplateError> {
+    let mut placeholders = Vec::new();
+    let mut offset = 0;
+    while let Some(start) = text[offset..].find("{{").map(|i| offset + i) {
+        let Some(len) = text[start + 2..].find("}}") else {
+            break;
+        };
+        let end = start + 2 + len + 2;
+        let inner = text[start + 2..end - 2].trim();
+        let (name, param_type) = match inner.split_once(':') {
+            Some((name, param_type)) => (name.trim(), Some(param_type.trim())),
+            None => (inner, None),
+        };
+        let valid_name = !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'));
+        if !valid_name {
+            offset = start + 2;
+            continue;
+        }
+        let param_type =
+            match param_type {
+                Some(param_type) => Some(ParamType::parse(param_type).ok_or_else(|| {
+                    TemplateError::InvalidPlaceholder(text[start..end].to_string())
+                })?),
+                None => None,
+            };
+        placeholders.push(Placeholder {
+            start,
+            end,
+            name,
+            param_type,
+        });
+        offset = end;
+    }
+    Ok(placeholders)
+}
+
+/// Collects the parameters of the placeholders in a value.
+fn collect_params(
+    value: &Value, params: &mut BTreeMap<String, Option<ParamType>>,
+) -> Result<(), TemplateError> {
+    match value {
+        Value::String(text) => {
+            for placeholder in placeholders(text)? {
+                let declared = params.entry(placeholder.name.to_string()).or_default();
+                match (*declared, placeholder.param_type) {
+                    (Some(a), Some(b)) if a != b => {
+                        return Err(TemplateError::InvalidPlaceholder(format!(
+                            "conflicting types {a} and {b} of {}",
+                            placeholder.name
+                        )));
+                    }
+                    (None, param_type) => *declared = param_type,
+                    _ => {}
+                }
+            }
+        }
+        Value::Array(values) => {
+            for value in values {
+                collect_params(value, params)?;
+            }
+        }
+        Value::Object(map) => {
+            for value in map.values() {
+                collect_params(value, params)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Substitutes the placeholders in a value, whose parameters have been
+/// checked to be present.
+fn render_value(value: &Value, params: &HashMap<String, Value>) -> Result<Value, TemplateError> {
+    Ok(match value {
+        Value::String(text) => {
+            let placeholders = placeholders(text)?;
+            match placeholders.as_slice() {
+                [] => value.clone(),
+                [placeholder] if placeholder.start == 0 && placeholder.end == text.len() => {
+                    params[placeholder.name].clone()
+                }
+                _ => {
+                    let mut rendered = String::new();
+                    let mut offset = 0;
+                    for placeholder in &placeholders {
+                        rendered.push_str(&text[offset..placeholder.start]);
+                        match &params[placeholder.name] {
+                            Value::String(s) => rendered.push_str(s),
+                            value @ (Value::Number(_) | Value::Bool(_)) => {
+                                rendered.push_str(&value.to_string())
+                            }
+                            value => {
+                                return Err(TemplateError::TypeMismatch {
+                                    name: placeholder.name.to_string(),
+                                    expected: ParamType::String,
+                                    value: value.clone(),
+                                });
+                            }
+                        }
+                        offset = placeholder.end;
+                    }
+                    rendered.push_str(&text[offset..]);
+                    Value::String(rendered)
+                }
+            }
+        }
+        Value::Array(values) => Value::Array(
+            values
+                .iter()
+                .map(|value| render_value(value, params))
+                .collect::<Result<_, _>>()?,
+        ),
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(key, value)| Ok((key.clone(), render_value(value, params)?)))
+                .collect::<Result<Map<_, _>, TemplateError>>()?,
+        ),
+        _ => value.clone(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ClientError;
+    use serde_json::json;
+
+    #[test]
+    fn test_render() {
+        let template = PromptTemplate::from_value(json!({
+            "3": {
+                "class_type": "KSampler",
+                "inputs": {"seed": "{{seed:int}}", "cfg": "{{ cfg }}", "model": ["4", 0]}
+            },
+            "6": {
+                "class_type": "CLIPTextEncode",
+                "inputs": {"text": "a photo of {{subject:string}}, {{style}} {{not a param}}"}
+            }
+        }))
+        .unwrap();
+        assert_eq!(
+            template.params().collect::<Vec<_>>(),
+            [
+                ("cfg", None),
+                ("seed", Some(ParamType::Int)),
+                ("style", None),
+                ("subject", Some(ParamType::String)),
+            ]
+        );
+
+        let mut params = HashMap::from([
+            ("seed".to_string(), json!(42)),
+            ("cfg".to_string(), json!(7.5)),
+            ("subject".to_string(), json!("a cat")),
+            ("style".to_string(), json!(35)),
+        ]);
+        assert_eq!(
+            template.render(&params).unwrap().into_value(),
+            json!({
+                "3": {
+                    "class_type": "KSampler",
+                    "inputs": {"seed": 42, "cfg": 7.5, "model": ["4", 0]}
+                },
+                "6": {
+                    "class_type": "CLIPTextEncode",
+                    "inputs": {"text": "a photo of a cat, 35 {{not a param}}"}
+                }
+            })
+        );
+
+        params.insert("seed".to_string(), json!("42"));
+        assert!(matches!(
+            template.render(&params),
+            Err(ClientError::Template(TemplateError::TypeMismatch { name, expected: ParamType::Int, .. }))
+                if name == "seed"
+        ));
+        params.remove("seed");
+        assert!(matches!(
+            template.render(&params),
+            Err(ClientError::Template(TemplateError::MissingParameter(name))) if name == "seed"
+        ));
+
+        assert!(PromptTemplate::from_value(json!({"text": "{{a:int}} {{a:string}}"})).is_err());
+        assert!(PromptTemplate::from_value(json!({"text": "{{a:date}}"})).is_err());
+    }
+}
@@ -0,0 +1,232 @@
+use crate::ClientResult;
+use tokio_tungstenite::{
+    Connector,
+    tungstenite::{self, error::TlsError},
+};
+
+/// A root certificate trusted in addition to the default roots when
+/// verifying the server certificate.
+///
+/// Allows connecting to deployments whose certificate is issued by a private
+/// certificate authority. See
+/// [`ClientBuilder::add_root_certificate`](crate::ClientBuilder::add_root_certificate).
+#[derive(Clone, Debug)]
+pub struct Certificate {
+    der: Vec<u8>,
+}
+
+impl Certificate {
+    /// Creates a [`Certificate`] from a DER encoded certificate.
+    ///
+    /// # Parameters
+    ///
+    /// - `der`: The DER encoded certificate.
+    ///
+    /// # Returns
+    ///
+    /// The certificate, or an error if it cannot be parsed.
+    pub fn from_der(der: &[u8]) -> ClientResult<Self> {
+        reqwest::Certificate::from_der(der)?;
+        Ok(Self { der: der.to_vec() })
+    }
+
+    /// Creates a [`Certificate`] from a PEM encoded certificate.
+    ///
+    /// If the PEM contains several certificates, only the first one is used.
+    ///
+    /// # Parameters
+    ///
+    /// - `pem`: The PEM encoded certificate.
+    ///
+    /// # Returns
+    ///
+    /// The certificate, or an error if it cannot be parsed.
+    pub fn from_pem(pem: &[u8]) -> ClientResult<Self> {
+        #[cfg(feature = "native-tls")]
+        let der = native_tls::Certificate::from_pem(pem)
+            .and_then(|cert| cert.to_der())
+            .map_err(|err| tungstenite::Error::Tls(TlsError::Native(err)))?;
+        #[cfg(not(feature = "native-tls"))]
+        let der = {
+            use rustls::pki_types::{CertificateDer, pem::PemObject};
+            CertificateDer::from_pem_slice(pem)
+                .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?
+                .to_vec()
+        };
+        Self::from_der(&der)
+    }
+
+    /// Converts the certificate for the HTTP client.
+    pub(crate) fn to_reqwest(&self) -> ClientResult<reqwest::Certificate> {
+        Ok(reqwest::Certificate::from_der(&self.der)?)
+    }
+}
+
+/// Builds the TLS connector of the websocket connection.
+///
+/// Used whenever the `native-tls` feature is enabled, even along with the
+/// `rustls` feature, like the HTTP client of reqwest does.
+///
+/// # Parameters
+///
+/// - `accept_invalid_certs`: Whether to skip the verification of the server
+///   certificate.
+/// - `root_certificates`: The root certificates trusted in addition to the
+///   default roots.
+///
+/// # Returns
+///
+/// The connector, or `None` to use the default connector of tokio-tungstenite.
+#[cfg(feature = "native-tls")]
+pub(crate) fn ws_tls_connector(
+    accept_invalid_certs: bool, root_certificates: &[Certificate],
+) -> ClientResult<Option<Connector>> {
+    if !accept_invalid_certs && root_certificates.is_empty() {
+        return Ok(None);
+    }
+    let tls_error = |err| tungstenite::Error::Tls(TlsError::Native(err));
+    let mut builder = native_tls::TlsConnector::builder();
+    builder.danger_accept_invalid_certs(accept_invalid_certs);
+    for cert in root_certificates {
+        builder
+            .add_root_certificate(native_tls::Certificate::from_der(&cert.der).map_err(tls_error)?);
+    }
+    let connector = builder.build().map_err(tls_error)?;
+    Ok(Some(Connector::NativeTls(connector)))
+}
+
+/// Builds the TLS connector of the websocket connection.
+///
+/// # Parameters
+///
+/// - `accept_invalid_certs`: Whether to skip the verification of the server
+///   certificate.
+/// - `root_certificates`: The root certificates trusted in addition to the
+///   default roots.
+///
+/// Unlike the default connector of tokio-tungstenite, the connector is built
+/// with the process-level crypto provider, which is installed if missing.
+///
+/// # Returns
+///
+/// The connector, which is always built.
+#[cfg(all(feature = "rustls", not(feature = "native-tls")))]
+pub(crate) fn ws_tls_connector(
+    accept_invalid_certs: bool, root_certificates: &[Certificate],
+) -> ClientResult<Option<Connector>> {
+    use rustls::{ClientConfig, RootCertStore, pki_types::CertificateDer};
+    use std::sync::Arc;
+
+    let tls_error = |err| tungstenite::Error::Tls(TlsError::Rustls(err));
+    let provider = crypto_provider();
+    let builder = ClientConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()
+        .map_err(tls_error)?;
+    let config = if accept_invalid_certs {
+        builder
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(danger::NoCertificateVerification(provider)))
+            .with_no_client_auth()
+    } else {
+        let mut roots = RootCertStore {
+            roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+        };
+        for cert in root_certificates {
+            roots
+                .add(CertificateDer::from(cert.der.clone()))
+                .map_err(tls_error)?;
+        }
+        builder.with_root_certificates(roots).with_no_client_auth()
+    };
+    Ok(Some(Connector::Rustls(Arc::new(config))))
+}
+
+/// Returns the process-level crypto provider of rustls, installing the one
+/// based on `ring` if none is installed yet.
+///
+/// Without an installed provider, rustls panics when several providers are
+/// enabled in the dependency graph, e.g. `aws-lc-rs` by another crate.
+#[cfg(all(feature = "rustls", not(feature = "native-tls")))]
+fn crypto_provider() -> std::sync::Arc<rustls::crypto::CryptoProvider> {
+    use rustls::crypto::CryptoProvider;
+    use std::sync::Arc;
+
+    if let Some(provider) = CryptoProvider::get_default() {
+        return provider.clone();
+    }
+    // Installing fails if another thread installed a provider in the meantime.
+    let _ = rustls::crypto::ring::default_provider().install_default();
+    CryptoProvider::get_default()
+        .cloned()
+        .unwrap_or_else(|| Arc::new(rustls::crypto::ring::default_provider()))
+}
+
+#[cfg(all(feature = "rustls", not(feature = "native-tls")))]
+mod danger {
+    use rustls::{
+        DigitallySignedStruct, Error, SignatureScheme,
+        client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier},
+        crypto::{CryptoProvider, verify_tls12_signature, verify_tls13_signature},
+        pki_types::{CertificateDer, ServerName, UnixTime},
+    };
+    use std::sync::Arc;
+
+    /// A verifier accepting any server certificate, while still checking the
+    /// handshake signatures.
+    #[derive(Debug)]
+    pub(super) struct NoCertificateVerification(pub(super) Arc<CryptoProvider>);
+
+    impl ServerCertVerifier for NoCertificateVerification {
+        fn verify_server_cert(
+            &self, _end_entity: &CertificateDer<'_>, _intermediates: &[CertificateDer<'_>],
+            _server_name: &ServerName<'_>, _ocsp_response: &[u8], _now: UnixTime,
+        ) -> Result<ServerCertVerified, Error> {
+            Ok(ServerCertVerified::assertion())
+        }
+
+        fn verify_tls12_signature(
+            &self, message: &[u8], cert: &CertificateDer<'_>, dss: &DigitallySignedStruct,
+        ) -> Result<HandshakeSignatureValid, Error> {
+            verify_tls12_signature(
+                message,
+                cert,
+                dss,
+                &self.0.signature_verification_algorithms,
+            )
+        }
+
+        fn verify_tls13_signature(
+            &self, message: &[u8], cert: &CertificateDer<'_>, dss: &DigitallySignedStruct,
+        ) -> Result<HandshakeSignatureValid, Error> {
+            verify_tls13_signature(
+                message,
+                cert,
+                dss,
+                &self.0.signature_verification_algorithms,
+            )
+        }
+
+        fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+            self.0.signature_verification_algorithms.supported_schemes()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ws_tls_connector() {
+        // The rustls connector is always built to use the installed crypto provider.
+        assert_eq!(
+            ws_tls_connector(false, &[]).unwrap().is_none(),
+            cfg!(feature = "native-tls")
+        );
+        assert!(ws_tls_connector(true, &[]).unwrap().is_some());
+
+        let cert = Certificate::from_pem(include_bytes!("../tests/data/ca.pem")).unwrap();
+        assert!(ws_tls_connector(false, &[cert]).unwrap().is_some());
+        assert!(Certificate::from_pem(b"not a certificate").is_err());
+    }
+}
@@ -0,0 +1,1350 @@
+use crate::{
+    ClientError, ClientResult, ComfyUIClient,
+    meta::{
+        FileInfo, InputSpec, InputType, NodeInfo, ObjectInfo, Prompt, UploadOptions,
+        compare_node_ids,
+    },
+    pipeline::input_file_path,
+};
+use rand_core::RngCore;
+use serde::{Serialize, Serializer, ser::SerializeTuple};
+use serde_json::{Map, Value, json};
+use std::{collections::HashMap, fmt, path::Path};
+
+/// The seed inputs of the built-in nodes sampling noise.
+const SEED_INPUTS: &[(&str, &str)] = &[
+    ("KSampler", "seed"),
+    ("KSamplerAdvanced", "noise_seed"),
+    ("SamplerCustom", "noise_seed"),
+    ("RandomNoise", "noise_seed"),
+];
+
+/// The names of seed inputs of other nodes, e.g. custom seed nodes.
+const SEED_INPUT_NAMES: &[&str] = &["seed", "noise_seed"];
+
+/// The exclusive upper bound of random seeds of other nodes, as their
+/// maximum is unknown and often lower than the one of the built-in samplers,
+/// e.g. the largest integer the web frontend represents exactly.
+const FALLBACK_SEED_LIMIT: u64 = 1 << 50;
+
+/// The nodes of the web frontend which only exist in the UI format.
+const UI_ONLY_NODES: &[&str] = &["Note", "MarkdownNote", "PrimitiveNode", "Reroute"];
+
+/// The types of inputs which the web frontend displays as widgets.
+const WIDGET_TYPES: &[&str] = &["INT", "FLOAT", "STRING", "BOOLEAN", "COMBO"];
+
+/// The maximum number of reroutes and bypassed nodes followed to resolve a
+/// link, which guards against cycles.
+const MAX_LINK_HOPS: usize = 64;
+
+/// The node mode of the web frontend for muted nodes.
+const MODE_MUTED: u64 = 2;
+
+/// The node mode of the web frontend for bypassed nodes.
+const MODE_BYPASSED: u64 = 4;
+
+/// A workflow graph in the API prompt format, built node by node.
+///
+/// This avoids templating the JSON of a workflow by hand: nodes are added
+/// with their inputs, and the outputs of a node are linked to the inputs of
+/// another one via [`NodeId::output`]. Existing workflows can be loaded via
+/// [`Workflow::from_json`] or converted from the UI format via
+/// [`Workflow::from_ui_json`], looked up by node class or title, and modified
+/// via [`Workflow::set_input`].
+///
+/// # Example
+///
+/// ```no_run
+/// # async fn run() -> comfyui_client::ClientResult<()> {
+/// use comfyui_client::{ClientBuilder, workflow::Workflow};
+/// use serde_json::json;
+///
+/// let client = ClientBuilder::new("http://localhost:8188").build_only_http().await?;
+///
+/// let mut workflow = Workflow::new();
+/// let checkpoint = workflow.add_node(
+///     "CheckpointLoaderSimple",
+///     json!({"ckpt_name": "v1-5-pruned-emaonly.safetensors"}),
+/// );
+/// let positive = workflow.add_node(
+///     "CLIPTextEncode",
+///     json!({"text": "a cat", "clip": checkpoint.output(1)}),
+/// );
+/// // ...
+/// client.post_prompt(&workflow).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Workflow {
+    nodes: Value,
+    next_id: u64,
+}
+
+impl Default for Workflow {
+    fn default() -> Self {
+        Self {
+            nodes: Value::Object(Map::new()),
+            next_id: 1,
+        }
+    }
+}
+
+impl Workflow {
+    /// Creates a new, empty [`Workflow`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a node to the workflow.
+    ///
+    /// # Parameters
+    ///
+    /// - `class_type`: The class of the node, e.g. `KSampler`.
+    /// - `inputs`: The inputs of the node as JSON object, where links to the
+    ///   outputs of other nodes are created via [`NodeId::output`].
+    ///
+    /// # Returns
+    ///
+    /// The [`NodeId`] of the added node.
+    pub fn add_node(&mut self, class_type: impl Into<String>, inputs: Value) -> NodeId {
+        let id = NodeId(self.next_id.to_string());
+        self.next_id += 1;
+        self.nodes_mut().insert(
+            id.0.clone(),
+            json!({"class_type": class_type.into(), "inputs": inputs}),
+        );
+        id
+    }
+
+    /// Adds a typed node to the workflow, e.g. one generated via
+    /// [`generate_nodes`](crate::codegen::generate_nodes).
+    ///
+    /// # Parameters
+    ///
+    /// - `node`: The node to add.
+    ///
+    /// # Returns
+    ///
+    /// The [`NodeId`] of the added node.
+    pub fn add(&mut self, node: &impl TypedNode) -> NodeId {
+        self.add_node(node.class_type(), node.inputs())
+    }
+
+    /// Parses an existing workflow in the API prompt format, e.g. exported
+    /// via "Export (API)" in the web frontend.
+    ///
+    /// # Parameters
+    ///
+    /// - `json`: The JSON of the workflow.
+    ///
+    /// # Returns
+    ///
+    /// The parsed [`Workflow`], or an error if the JSON isn't an object.
+    pub fn from_json(json: &str) -> ClientResult<Self> {
+        Ok(Self::from_nodes(serde_json::from_str(json)?))
+    }
+
+    /// Wraps an existing workflow in the API prompt format.
+    ///
+    /// # Parameters
+    ///
+    /// - `value`: The workflow as JSON value.
+    ///
+    /// # Returns
+    ///
+    /// The [`Workflow`], or an error if the value isn't an object.
+    pub fn from_value(value: Value) -> ClientResult<Self> {
+        Ok(Self::from_nodes(serde_json::from_value(value)?))
+    }
+
+    /// Converts a workflow in the UI format, as saved by the web frontend,
+    /// into the API prompt format.
+    ///
+    /// The widget values of the nodes are mapped to their inputs using the
+    /// node schemas of the server. Notes as well as muted nodes are dropped,
+    /// reroutes and primitive nodes are resolved, and links through bypassed
+    /// nodes are passed through to the input of the same type. Group nodes
+    /// and subgraphs aren't supported.
+    ///
+    /// # Parameters
+    ///
+    /// - `json`: The JSON of the workflow in the UI format.
+    /// - `object_info`: The node schemas, as returned by
+    ///   [`ComfyUIClient::get_object_info`](crate::ComfyUIClient::get_object_info).
+    ///
+    /// # Returns
+    ///
+    /// The converted [`Workflow`], or an error if the JSON isn't a workflow
+    /// in the UI format or contains a node whose class is unknown.
+    pub fn from_ui_json(json: &str, object_info: &ObjectInfo) -> ClientResult<Self> {
+        Self::from_ui_value(&serde_json::from_str(json)?, object_info)
+    }
+
+    /// Converts a workflow in the UI format into the API prompt format, see
+    /// [`Workflow::from_ui_json`].
+    ///
+    /// # Parameters
+    ///
+    /// - `value`: The workflow in the UI format as JSON value.
+    /// - `object_info`: The node schemas of the server.
+    ///
+    /// # Returns
+    ///
+    /// The converted [`Workflow`], or an error.
+    pub fn from_ui_value(value: &Value, object_info: &ObjectInfo) -> ClientResult<Self> {
+        let invalid = |message: &str| ClientError::InvalidUiWorkflow(message.to_string());
+        let nodes = value["nodes"]
+            .as_array()
+            .ok_or_else(|| invalid("missing nodes"))?;
+        let nodes = nodes
+            .iter()
+            .map(|node| {
+                Ok((
+                    ui_id(&node["id"]).ok_or_else(|| invalid("missing node id"))?,
+                    node,
+                ))
+            })
+            .collect::<ClientResult<HashMap<_, _>>>()?;
+        let links = value["links"]
+            .as_array()
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+            .iter()
+            .filter_map(UiLink::parse)
+            .map(|link| (link.id.clone(), link))
+            .collect::<HashMap<_, _>>();
+        let graph = UiGraph { nodes, links };
+
+        let mut converted = Map::new();
+        for (id, node) in &graph.nodes {
+            let class_type = node["type"].as_str().unwrap_or_default();
+            let mode = node["mode"].as_u64().unwrap_or_default();
+            if UI_ONLY_NODES.contains(&class_type) || mode == MODE_MUTED || mode == MODE_BYPASSED {
+                continue;
+            }
+            let Some(info) = object_info.get(class_type) else {
+                return Err(ClientError::InvalidUiWorkflow(format!(
+                    "node {id} has unknown class type {class_type:?}"
+                )));
+            };
+
+            let mut inputs = widget_inputs(node, info);
+            for input in node["inputs"]
+                .as_array()
+                .map(Vec::as_slice)
+                .unwrap_or_default()
+            {
+                let Some(name) = input["name"].as_str() else {
+                    continue;
+                };
+                if let Some((origin, slot)) = graph.resolve(&input["link"]) {
+                    inputs.insert(name.to_string(), json!([origin, slot]));
+                }
+            }
+
+            let title = match node["title"].as_str() {
+                Some(title) => title,
+                None if info.display_name.is_empty() => class_type,
+                None => &info.display_name,
+            };
+            converted.insert(
+                id.clone(),
+                json!({
+                    "inputs": inputs,
+                    "class_type": class_type,
+                    "_meta": {"title": title},
+                }),
+            );
+        }
+        Ok(Self::from_nodes(converted))
+    }
+
+    fn from_nodes(nodes: Map<String, Value>) -> Self {
+        let max_id = nodes.keys().filter_map(|id| id.parse::<u64>().ok()).max();
+        Self {
+            nodes: Value::Object(nodes),
+            next_id: max_id.map_or(1, |id| id + 1),
+        }
+    }
+
+    /// Returns the node with the given identifier.
+    pub fn node_by_id(&self, id: &str) -> Option<Node<'_>> {
+        let (id, value) = self.nodes().get_key_value(id)?;
+        Some(Node {
+            id: NodeId(id.clone()),
+            value,
+        })
+    }
+
+    /// Returns the nodes of the given class, e.g. `KSampler`, ordered by
+    /// identifier.
+    pub fn nodes_by_class(&self, class_type: &str) -> Vec<Node<'_>> {
+        let mut nodes = self
+            .iter()
+            .filter(|node| node.class_type() == Some(class_type))
+            .collect::<Vec<_>>();
+        nodes.sort_by(|a, b| compare_node_ids(a.id.as_str(), b.id.as_str()));
+        nodes
+    }
+
+    /// Returns the node with the given title, as set in the web frontend.
+    ///
+    /// If several nodes share the title, the one with the lowest identifier is
+    /// returned.
+    pub fn node_by_title(&self, title: &str) -> Option<Node<'_>> {
+        self.iter()
+            .filter(|node| node.title() == Some(title))
+            .min_by(|a, b| compare_node_ids(a.id.as_str(), b.id.as_str()))
+    }
+
+    /// Sets an input of a node, replacing its previous value.
+    ///
+    /// # Parameters
+    ///
+    /// - `node`: The identifier of the node.
+    /// - `name`: The name of the input, e.g. `text`.
+    /// - `value`: The value of the input, which may be a [`Link`] to the output
+    ///   of another node.
+    ///
+    /// # Returns
+    ///
+    /// An error if the node is not found.
+    pub fn set_input(
+        &mut self, node: impl AsRef<str>, name: impl Into<String>, value: impl Into<Value>,
+    ) -> ClientResult<()> {
+        let node = node.as_ref();
+        let Some(Value::Object(entry)) = self.nodes_mut().get_mut(node) else {
+            return Err(ClientError::NodeNotFound(node.to_string()));
+        };
+        let inputs = entry
+            .entry("inputs")
+            .or_insert_with(|| Value::Object(Map::new()));
+        if !inputs.is_object() {
+            *inputs = Value::Object(Map::new());
+        }
+        inputs[name.into()] = value.into();
+        Ok(())
+    }
+
+    /// Uploads a local image and sets it as the `image` input of a node, e.g.
+    /// a `LoadImage` node.
+    ///
+    /// The image is uploaded into the input directory via
+    /// [`ComfyUIClient::upload_image_from_path`]. As existing files aren't
+    /// overwritten, the server may store the image under another name, which
+    /// is the one referenced by the input.
+    ///
+    /// # Parameters
+    ///
+    /// - `node`: The identifier of the node.
+    /// - `client`: The client to upload the image with.
+    /// - `path`: The path of the local image.
+    ///
+    /// # Returns
+    ///
+    /// The [`FileInfo`] of the uploaded image, or an error if the node is not
+    /// found or the upload fails.
+    pub async fn attach_input_image(
+        &mut self, node: impl AsRef<str>, client: &ComfyUIClient, path: impl AsRef<Path>,
+    ) -> ClientResult<FileInfo> {
+        let node = node.as_ref();
+        if !self.nodes().contains_key(node) {
+            return Err(ClientError::NodeNotFound(node.to_string()));
+        }
+        let info = client
+            .upload_image_from_path(path, &UploadOptions::default())
+            .await?;
+        self.set_input(node, "image", input_file_path(&info))?;
+        Ok(info)
+    }
+
+    /// Sets the seed of a node, e.g. a `KSampler`.
+    ///
+    /// The seed input is found by the class of the node, falling back to an
+    /// input named `seed` or `noise_seed` holding a number for other nodes.
+    ///
+    /// # Parameters
+    ///
+    /// - `node`: The identifier of the node.
+    /// - `seed`: The seed to set.
+    ///
+    /// # Returns
+    ///
+    /// An error if the node is not found or has no seed input, e.g. because
+    /// its seed is linked to the output of another node.
+    pub fn set_seed(&mut self, node: impl AsRef<str>, seed: u64) -> ClientResult<()> {
+        let node = node.as_ref();
+        let Some(entry) = self.nodes().get(node) else {
+            return Err(ClientError::NodeNotFound(node.to_string()));
+        };
+        let Some(name) = seed_input(entry) else {
+            return Err(ClientError::NoSeedInput(node.to_string()));
+        };
+        self.set_input(node, name, seed)
+    }
+
+    /// Sets the seeds of all nodes with a seed input to random values, so
+    /// each submission of the workflow generates a different result.
+    ///
+    /// See [`set_seed`](Self::set_seed) for how seed inputs are found. Seeds
+    /// of the built-in samplers span the full `u64` range, while seeds of
+    /// other nodes are kept below 2^50, which their maximum usually allows.
+    ///
+    /// # Parameters
+    ///
+    /// - `rng`: The random number generator, e.g. `rand::rng()`.
+    ///
+    /// # Returns
+    ///
+    /// The identifiers of the updated nodes along with their new seed,
+    /// ordered by identifier, e.g. to reproduce a result later.
+    pub fn randomize_seeds(&mut self, rng: &mut impl RngCore) -> Vec<(NodeId, u64)> {
+        let mut ids = self
+            .iter()
+            .filter(|node| seed_input(node.value).is_some())
+            .map(|node| {
+                let class_type = node.class_type().unwrap_or_default();
+                let builtin = SEED_INPUTS.iter().any(|(class, _)| *class == class_type);
+                (node.id, builtin)
+            })
+            .collect::<Vec<_>>();
+        ids.sort_by(|(a, _), (b, _)| compare_node_ids(a.as_str(), b.as_str()));
+        ids.into_iter()
+            .map(|(id, builtin)| {
+                let seed = if builtin {
+                    rng.next_u64()
+                } else {
+                    rng.next_u64() % FALLBACK_SEED_LIMIT
+                };
+                // The node exists and has a seed input, as checked above.
+                let _ = self.set_seed(&id, seed);
+                (id, seed)
+            })
+            .collect()
+    }
+
+    /// Validates the workflow against the node schemas of a server, before
+    /// submitting it.
+    ///
+    /// Checks that the classes of the nodes exist, required inputs are
+    /// present, values of combo inputs such as model names or samplers are
+    /// allowed, and links point to existing outputs of a compatible type.
+    /// Inputs unknown to the schema are ignored, like the server does.
+    ///
+    /// # Parameters
+    ///
+    /// - `object_info`: The node schemas, as returned by
+    ///   [`ComfyUIClient::get_object_info`](crate::ComfyUIClient::get_object_info).
+    ///
+    /// # Returns
+    ///
+    /// The issues found, ordered by node identifier and input name, which is
+    /// empty if the workflow is valid.
+    pub fn validate(&self, object_info: &ObjectInfo) -> Vec<WorkflowIssue> {
+        let mut issues = Vec::new();
+        for node in self.iter() {
+            let issue = |input: Option<&str>, kind| WorkflowIssue {
+                node: node.id.clone(),
+                input: input.map(ToOwned::to_owned),
+                kind,
+            };
+            let class_type = node.class_type().unwrap_or_default();
+            let Some(info) = object_info.get(class_type) else {
+                issues.push(issue(
+                    None,
+                    WorkflowIssueKind::UnknownClass(class_type.to_string()),
+                ));
+                continue;
+            };
+
+            for name in info.input.required.keys() {
+                if node.input(name).is_none() {
+                    issues.push(issue(Some(name), WorkflowIssueKind::MissingInput));
+                }
+            }
+
+            let inputs = node.value["inputs"].as_object().into_iter().flatten();
+            for (name, value) in inputs {
+                let Some(spec) = info.input.get(name) else {
+                    continue;
+                };
+                if let Some((linked_id, index)) = as_link(value) {
+                    let Some(linked) = self.node_by_id(linked_id) else {
+                        issues.push(issue(
+                            Some(name),
+                            WorkflowIssueKind::InvalidLink {
+                                node: linked_id.to_string(),
+                                index,
+                            },
+                        ));
+                        continue;
+                    };
+                    // Unknown classes of linked nodes are reported on their own.
+                    let linked_class = linked.class_type().unwrap_or_default();
+                    let Some(linked_info) = object_info.get(linked_class) else {
+                        continue;
+                    };
+                    match (linked_info.output.get(index), &spec.r#type) {
+                        (None, _) => issues.push(issue(
+                            Some(name),
+                            WorkflowIssueKind::InvalidLink {
+                                node: linked_id.to_string(),
+                                index,
+                            },
+                        )),
+                        (Some(actual), InputType::Named(expected))
+                            if expected != "COMBO" && !types_compatible(actual, expected) =>
+                        {
+                            issues.push(issue(
+                                Some(name),
+                                WorkflowIssueKind::TypeMismatch {
+                                    expected: expected.clone(),
+                                    actual: actual.clone(),
+                                },
+                            ))
+                        }
+                        _ => {}
+                    }
+                } else if let Some(available) = spec.combo_values() {
+                    if !available.contains(value) {
+                        issues.push(issue(
+                            Some(name),
+                            WorkflowIssueKind::InvalidValue {
+                                value: value.clone(),
+                                available: available.to_vec(),
+                            },
+                        ));
+                    }
+                }
+            }
+        }
+        issues.sort_by(|a, b| {
+            compare_node_ids(a.node.as_str(), b.node.as_str()).then_with(|| a.input.cmp(&b.input))
+        });
+        issues
+    }
+
+    /// Returns an iterator over the nodes of the workflow.
+    pub fn iter(&self) -> impl Iterator<Item = Node<'_>> {
+        self.nodes().iter().map(|(id, value)| Node {
+            id: NodeId(id.clone()),
+            value,
+        })
+    }
+
+    /// Returns the workflow in the API prompt format.
+    pub fn as_value(&self) -> &Value {
+        &self.nodes
+    }
+
+    /// Converts the workflow into the API prompt format.
+    pub fn into_value(self) -> Value {
+        self.nodes
+    }
+
+    fn nodes(&self) -> &Map<String, Value> {
+        match &self.nodes {
+            Value::Object(nodes) => nodes,
+            _ => unreachable!("nodes of workflow are always an object"),
+        }
+    }
+
+    fn nodes_mut(&mut self) -> &mut Map<String, Value> {
+        match &mut self.nodes {
+            Value::Object(nodes) => nodes,
+            _ => unreachable!("nodes of workflow are always an object"),
+        }
+    }
+}
+
+impl Serialize for Workflow {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.nodes.serialize(serializer)
+    }
+}
+
+impl<'a> From<&'a Workflow> for Prompt<'a> {
+    fn from(value: &'a Workflow) -> Self {
+        Prompt::Value(&value.nodes)
+    }
+}
+
+/// A node of a [`Workflow`], obtained from one of its lookup methods.
+#[derive(Clone, Debug)]
+pub struct Node<'a> {
+    id: NodeId,
+    value: &'a Value,
+}
+
+impl<'a> Node<'a> {
+    /// Returns the identifier of the node.
+    pub fn id(&self) -> &NodeId {
+        &self.id
+    }
+
+    /// Returns the class of the node, e.g. `KSampler`.
+    pub fn class_type(&self) -> Option<&'a str> {
+        self.value["class_type"].as_str()
+    }
+
+    /// Returns the title of the node, as set in the web frontend.
+    pub fn title(&self) -> Option<&'a str> {
+        self.value["_meta"]["title"].as_str()
+    }
+
+    /// Returns the value of an input of the node, which is `[node_id, index]`
+    /// for links to the output of another node.
+    pub fn input(&self, name: &str) -> Option<&'a Value> {
+        self.value["inputs"].get(name)
+    }
+
+    /// Returns the seed of the node, if it has a seed input holding a
+    /// number, see [`Workflow::set_seed`].
+    pub fn seed(&self) -> Option<u64> {
+        self.input(seed_input(self.value)?)?.as_u64()
+    }
+
+    /// Returns a link to an output of the node, see [`NodeId::output`].
+    pub fn output(&self, index: u32) -> Link {
+        self.id.output(index)
+    }
+}
+
+/// An issue of a [`Workflow`], found by [`Workflow::validate`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct WorkflowIssue {
+    /// The identifier of the node with the issue.
+    pub node: NodeId,
+    /// The name of the input with the issue, if the issue concerns an input.
+    pub input: Option<String>,
+    /// The kind of the issue.
+    pub kind: WorkflowIssueKind,
+}
+
+impl fmt::Display for WorkflowIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "node {}", self.node)?;
+        if let Some(input) = &self.input {
+            write!(f, ", input {input}")?;
+        }
+        match &self.kind {
+            WorkflowIssueKind::UnknownClass(class_type) => {
+                write!(f, ": unknown class type {class_type:?}")
+            }
+            WorkflowIssueKind::MissingInput => f.write_str(": required input is missing"),
+            WorkflowIssueKind::InvalidValue { value, .. } => {
+                write!(f, ": value {value} is not in the list of allowed values")
+            }
+            WorkflowIssueKind::InvalidLink { node, index } => {
+                write!(f, ": linked output {index} of node {node} doesn't exist")
+            }
+            WorkflowIssueKind::TypeMismatch { expected, actual } => {
+                write!(f, ": expected type {expected}, got {actual}")
+            }
+        }
+    }
+}
+
+/// The kind of a [`WorkflowIssue`].
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum WorkflowIssueKind {
+    /// The class of the node doesn't exist on the server, e.g. because a
+    /// custom node isn't installed.
+    UnknownClass(String),
+    /// A required input is missing.
+    MissingInput,
+    /// The value of a combo input, e.g. a model name, isn't among the allowed
+    /// values.
+    InvalidValue {
+        /// The value of the input.
+        value: Value,
+        /// The values allowed by the server.
+        available: Vec<Value>,
+    },
+    /// The input is linked to a node or output which doesn't exist.
+    InvalidLink {
+        /// The identifier of the linked node.
+        node: String,
+        /// The index of the linked output.
+        index: usize,
+    },
+    /// The input is linked to an output of an incompatible type.
+    TypeMismatch {
+        /// The type of the input.
+        expected: String,
+        /// The type of the linked output.
+        actual: String,
+    },
+}
+
+/// Returns the linked node and output index if the input value is a link.
+fn as_link(value: &Value) -> Option<(&str, usize)> {
+    match value.as_array()?.as_slice() {
+        [Value::String(node), Value::Number(index)] => Some((node, index.as_u64()? as usize)),
+        _ => None,
+    }
+}
+
+/// Checks whether an output type can be linked to an input type, following
+/// the rules of the server: `*` matches any type, and the types of an output
+/// listing several types separated by commas must all be accepted.
+fn types_compatible(output: &str, input: &str) -> bool {
+    if output == "*" || input == "*" || output == input {
+        return true;
+    }
+    let accepted = input.split(',').map(str::trim).collect::<Vec<_>>();
+    output
+        .split(',')
+        .map(str::trim)
+        .all(|ty| accepted.contains(&ty))
+}
+
+/// The nodes and links of a workflow in the UI format.
+struct UiGraph<'a> {
+    nodes: HashMap<String, &'a Value>,
+    links: HashMap<String, UiLink>,
+}
+
+impl UiGraph<'_> {
+    /// Resolves a link to the node and output slot in the API prompt format,
+    /// following reroutes and bypassed nodes.
+    ///
+    /// Returns `None` for missing links and links from nodes which don't
+    /// exist in the API prompt format, such as primitive nodes whose value
+    /// is kept in the widget of the target node.
+    fn resolve(&self, link_id: &Value) -> Option<(String, u64)> {
+        let mut link = self.links.get(&ui_id(link_id)?)?;
+        for _ in 0..MAX_LINK_HOPS {
+            let origin = self.nodes.get(&link.origin_id)?;
+            let origin_inputs = origin["inputs"].as_array().map(Vec::as_slice);
+            let origin_inputs = origin_inputs.unwrap_or_default();
+            let passthrough = if origin["type"] == "Reroute" {
+                origin_inputs.first()
+            } else if origin["mode"].as_u64() == Some(MODE_BYPASSED) {
+                let output_type = &origin["outputs"][link.origin_slot as usize]["type"];
+                origin_inputs
+                    .iter()
+                    .find(|input| &input["type"] == output_type && !input["link"].is_null())
+            } else if origin["mode"].as_u64() == Some(MODE_MUTED)
+                || origin["type"]
+                    .as_str()
+                    .is_some_and(|ty| UI_ONLY_NODES.contains(&ty))
+            {
+                return None;
+            } else {
+                return Some((link.origin_id.clone(), link.origin_slot));
+            };
+            link = self.links.get(&ui_id(&passthrough?["link"])?)?;
+        }
+        None
+    }
+}
+
+/// A link of a workflow in the UI format.
+struct UiLink {
+    id: String,
+    origin_id: String,
+    origin_slot: u64,
+}
+
+impl UiLink {
+    /// Parses a link, represented either as array
+    /// `[id, origin_id, origin_slot, target_id, target_slot, type]` or as
+    /// object with these fields.
+    fn parse(value: &Value) -> Option<Self> {
+        let (id, origin_id, origin_slot) = match value {
+            Value::Array(items) => (items.first()?, items.get(1)?, items.get(2)?),
+            _ => (&value["id"], &value["origin_id"], &value["origin_slot"]),
+        };
+        Some(Self {
+            id: ui_id(id)?,
+            origin_id: ui_id(origin_id)?,
+            origin_slot: origin_slot.as_u64()?,
+        })
+    }
+}
+
+/// Returns the identifier of a node or link of the UI format, which is
+/// usually a number, as string.
+fn ui_id(value: &Value) -> Option<String> {
+    match value {
+        Value::Number(id) => Some(id.to_string()),
+        Value::String(id) => Some(id.clone()),
+        _ => None,
+    }
+}
+
+/// Maps the widget values of a node in the UI format to the inputs of the
+/// node.
+///
+/// Widget values are either listed in the order of the inputs in the schema,
+/// followed by the control mode (e.g. `randomize`) for seed inputs, or keyed
+/// by input name.
+fn widget_inputs(node: &Value, info: &NodeInfo) -> Map<String, Value> {
+    let mut inputs = Map::new();
+    let widgets_values = &node["widgets_values"];
+    if let Value::Object(values) = widgets_values {
+        for (name, value) in values {
+            if info.input.get(name).is_some() {
+                inputs.insert(name.clone(), value.clone());
+            }
+        }
+        return inputs;
+    }
+
+    let mut values = widgets_values
+        .as_array()
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+        .iter();
+    for name in input_order(info) {
+        let Some(spec) = info.input.get(name) else {
+            continue;
+        };
+        if !is_widget(spec) {
+            continue;
+        }
+        let Some(value) = values.next() else {
+            break;
+        };
+        inputs.insert(name.to_string(), value.clone());
+        if has_control_widget(name, spec) {
+            values.next();
+        }
+    }
+    inputs
+}
+
+/// Returns the names of the required and optional inputs of a node in the
+/// order of their widgets, falling back to alphabetical order for servers
+/// which don't report the order.
+fn input_order(info: &NodeInfo) -> Vec<&str> {
+    let order = ["required", "optional"]
+        .into_iter()
+        .flat_map(|group| info.input_order.get(group).into_iter().flatten())
+        .map(String::as_str)
+        .collect::<Vec<_>>();
+    if !order.is_empty() {
+        return order;
+    }
+    let mut required = info
+        .input
+        .required
+        .keys()
+        .map(String::as_str)
+        .collect::<Vec<_>>();
+    let mut optional = info
+        .input
+        .optional
+        .keys()
+        .map(String::as_str)
+        .collect::<Vec<_>>();
+    required.sort_unstable();
+    optional.sort_unstable();
+    required.into_iter().chain(optional).collect()
+}
+
+/// Checks whether the web frontend displays an input as widget.
+fn is_widget(spec: &InputSpec) -> bool {
+    let widget_type = match &spec.r#type {
+        InputType::Combo(_) => true,
+        InputType::Named(name) => WIDGET_TYPES.contains(&name.as_str()),
+    };
+    widget_type && spec.options.others.get("forceInput") != Some(&Value::Bool(true))
+}
+
+/// Checks whether the web frontend adds a widget controlling the value after
+/// each generation, e.g. `randomize`, whose value follows the one of the
+/// input.
+fn has_control_widget(name: &str, spec: &InputSpec) -> bool {
+    if spec.options.others.get("control_after_generate") == Some(&Value::Bool(true)) {
+        return true;
+    }
+    matches!(&spec.r#type, InputType::Named(ty) if ty == "INT") && SEED_INPUT_NAMES.contains(&name)
+}
+
+/// Returns the name of the seed input of a node, unless the seed is linked
+/// to the output of another node.
+fn seed_input(node: &Value) -> Option<&'static str> {
+    let inputs = &node["inputs"];
+    let class_type = node["class_type"].as_str()?;
+    match SEED_INPUTS.iter().find(|(class, _)| *class == class_type) {
+        Some((_, name)) => (!inputs[name].is_array()).then_some(*name),
+        None => SEED_INPUT_NAMES
+            .iter()
+            .copied()
+            .find(|name| inputs[name].is_number()),
+    }
+}
+
+/// The identifier of a node in a [`Workflow`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NodeId(String);
+
+impl NodeId {
+    /// Returns the identifier as string, as used in the API prompt format and
+    /// in events.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Returns a link to an output of the node, to be used as the input of
+    /// another node.
+    ///
+    /// # Parameters
+    ///
+    /// - `index`: The index of the output, e.g. `1` for the `CLIP` output of
+    ///   `CheckpointLoaderSimple`.
+    pub fn output(&self, index: u32) -> Link {
+        Link {
+            node: self.clone(),
+            index,
+        }
+    }
+}
+
+impl AsRef<str> for NodeId {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for NodeId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Serialize for NodeId {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize(serializer)
+    }
+}
+
+/// A node with typed inputs, usually generated via
+/// [`generate_nodes`](crate::codegen::generate_nodes) and added to a workflow
+/// via [`Workflow::add`].
+pub trait TypedNode {
+    /// The class of the node, e.g. `KSampler`.
+    const CLASS_TYPE: &'static str;
+
+    /// Returns the class of the node.
+    fn class_type(&self) -> &'static str {
+        Self::CLASS_TYPE
+    }
+
+    /// Returns the inputs of the node as JSON object.
+    fn inputs(&self) -> Value;
+}
+
+/// A link to an output of a node, obtained from [`NodeId::output`].
+///
+/// Serializes to `[node_id, index]`, the format of links in the API prompt
+/// format.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Link {
+    node: NodeId,
+    index: u32,
+}
+
+impl Link {
+    /// Returns the node whose output is linked.
+    pub fn node(&self) -> &NodeId {
+        &self.node
+    }
+
+    /// Returns the index of the linked output.
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+}
+
+impl Serialize for Link {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut tuple = serializer.serialize_tuple(2)?;
+        tuple.serialize_element(&self.node)?;
+        tuple.serialize_element(&self.index)?;
+        tuple.end()
+    }
+}
+
+impl From<Link> for Value {
+    fn from(link: Link) -> Self {
+        json!([link.node.0, link.index])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_node() {
+        let mut workflow = Workflow::new();
+        let checkpoint = workflow.add_node(
+            "CheckpointLoaderSimple",
+            json!({"ckpt_name": "model.safetensors"}),
+        );
+        let positive = workflow.add_node(
+            "CLIPTextEncode",
+            json!({"text": "a cat", "clip": checkpoint.output(1)}),
+        );
+        assert_eq!(positive.as_str(), "2");
+        workflow.set_input(&positive, "text", "a dog").unwrap();
+        assert_eq!(
+            workflow.into_value(),
+            json!({
+                "1": {
+                    "class_type": "CheckpointLoaderSimple",
+                    "inputs": {"ckpt_name": "model.safetensors"}
+                },
+                "2": {
+                    "class_type": "CLIPTextEncode",
+                    "inputs": {"text": "a dog", "clip": ["1", 1]}
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn test_from_json() {
+        let mut workflow = Workflow::from_json(
+            r#"{
+                "3": {"class_type": "KSampler", "inputs": {"seed": 1, "model": ["4", 0]}},
+                "4": {"class_type": "CheckpointLoaderSimple", "inputs": {}},
+                "6": {
+                    "class_type": "CLIPTextEncode",
+                    "inputs": {"text": "a cat", "clip": ["4", 1]},
+                    "_meta": {"title": "Positive Prompt"}
+                },
+                "10": {"class_type": "KSampler", "inputs": {"seed": 2, "model": ["4", 0]}}
+            }"#,
+        )
+        .unwrap();
+
+        let node = workflow.node_by_id("3").unwrap();
+        assert_eq!(node.class_type(), Some("KSampler"));
+        assert_eq!(node.input("seed"), Some(&json!(1)));
+        let samplers = workflow.nodes_by_class("KSampler");
+        let ids = samplers
+            .iter()
+            .map(|node| node.id().as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, ["3", "10"]);
+
+        let positive = workflow
+            .node_by_title("Positive Prompt")
+            .unwrap()
+            .id()
+            .clone();
+        workflow.set_input(&positive, "text", "a dog").unwrap();
+        assert_eq!(workflow.as_value()["6"]["inputs"]["text"], "a dog");
+        assert!(matches!(
+            workflow.set_input("99", "text", "a dog"),
+            Err(ClientError::NodeNotFound(node)) if node == "99"
+        ));
+
+        let node = workflow.add_node("SaveImage", json!({"images": ["8", 0]}));
+        assert_eq!(node.as_str(), "11");
+        assert!(Workflow::from_json("[]").is_err());
+    }
+
+    #[test]
+    fn test_validate() {
+        let object_info = serde_json::from_value::<ObjectInfo>(json!({
+            "CheckpointLoaderSimple": {
+                "input": {"required": {"ckpt_name": [["model.safetensors"]]}},
+                "output": ["MODEL", "CLIP", "VAE"]
+            },
+            "CLIPTextEncode": {
+                "input": {"required": {"text": ["STRING", {}], "clip": ["CLIP"]}},
+                "output": ["CONDITIONING"]
+            },
+            "KSampler": {
+                "input": {
+                    "required": {
+                        "model": ["MODEL"],
+                        "positive": ["CONDITIONING"],
+                        "sampler_name": ["COMBO", {"options": ["euler", "dpmpp_2m"]}]
+                    }
+                },
+                "output": ["LATENT"]
+            }
+        }))
+        .unwrap();
+
+        let mut workflow = Workflow::new();
+        let checkpoint = workflow.add_node(
+            "CheckpointLoaderSimple",
+            json!({"ckpt_name": "model.safetensors"}),
+        );
+        let positive = workflow.add_node(
+            "CLIPTextEncode",
+            json!({"text": "a cat", "clip": checkpoint.output(1)}),
+        );
+        let sampler = workflow.add_node(
+            "KSampler",
+            json!({
+                "model": checkpoint.output(0),
+                "positive": positive.output(0),
+                "sampler_name": "euler"
+            }),
+        );
+        assert!(workflow.validate(&object_info).is_empty());
+
+        workflow
+            .set_input(&checkpoint, "ckpt_name", "other.safetensors")
+            .unwrap();
+        workflow
+            .set_input(&positive, "clip", checkpoint.output(5))
+            .unwrap();
+        workflow
+            .set_input(&sampler, "positive", checkpoint.output(1))
+            .unwrap();
+        workflow
+            .set_input(&sampler, "model", NodeId("9".to_string()).output(0))
+            .unwrap();
+        workflow.add_node("UpscaleModelLoader", json!({}));
+        workflow.add_node("CLIPTextEncode", json!({"clip": checkpoint.output(1)}));
+
+        let issues = workflow.validate(&object_info);
+        let kinds = issues
+            .iter()
+            .map(|issue| {
+                (
+                    issue.node.as_str(),
+                    issue.input.as_deref(),
+                    issue.kind.clone(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            [
+                (
+                    "1",
+                    Some("ckpt_name"),
+                    WorkflowIssueKind::InvalidValue {
+                        value: json!("other.safetensors"),
+                        available: vec![json!("model.safetensors")],
+                    }
+                ),
+                (
+                    "2",
+                    Some("clip"),
+                    WorkflowIssueKind::InvalidLink {
+                        node: "1".to_string(),
+                        index: 5
+                    }
+                ),
+                (
+                    "3",
+                    Some("model"),
+                    WorkflowIssueKind::InvalidLink {
+                        node: "9".to_string(),
+                        index: 0
+                    }
+                ),
+                (
+                    "3",
+                    Some("positive"),
+                    WorkflowIssueKind::TypeMismatch {
+                        expected: "CONDITIONING".to_string(),
+                        actual: "CLIP".to_string()
+                    }
+                ),
+                (
+                    "4",
+                    None,
+                    WorkflowIssueKind::UnknownClass("UpscaleModelLoader".to_string())
+                ),
+                ("5", Some("text"), WorkflowIssueKind::MissingInput),
+            ]
+        );
+        assert_eq!(
+            issues[3].to_string(),
+            "node 3, input positive: expected type CONDITIONING, got CLIP"
+        );
+        assert!(types_compatible("IMAGE", "IMAGE,MASK"));
+        assert!(!types_compatible("IMAGE,MASK", "IMAGE"));
+    }
+
+    #[test]
+    fn test_from_ui_json() {
+        let object_info = serde_json::from_value::<ObjectInfo>(json!({
+            "CheckpointLoaderSimple": {
+                "input": {"required": {"ckpt_name": [["model.safetensors"]]}},
+                "input_order": {"required": ["ckpt_name"]},
+                "output": ["MODEL", "CLIP", "VAE"],
+                "display_name": "Load Checkpoint"
+            },
+            "CLIPTextEncode": {
+                "input": {"required": {"text": ["STRING", {"multiline": true}], "clip": ["CLIP"]}},
+                "input_order": {"required": ["text", "clip"]},
+                "output": ["CONDITIONING"]
+            },
+            "LoraLoaderModelOnly": {
+                "input": {"required": {"model": ["MODEL"], "lora_name": [["lora.safetensors"]]}},
+                "input_order": {"required": ["model", "lora_name"]},
+                "output": ["MODEL"]
+            },
+            "KSampler": {
+                "input": {
+                    "required": {
+                        "model": ["MODEL"],
+                        "seed": ["INT", {"control_after_generate": true}],
+                        "steps": ["INT", {}],
+                        "positive": ["CONDITIONING"]
+                    }
+                },
+                "input_order": {"required": ["model", "seed", "steps", "positive"]},
+                "output": ["LATENT"]
+            }
+        }))
+        .unwrap();
+
+        let workflow = Workflow::from_ui_json(
+            r#"{
+                "nodes": [
+                    {
+                        "id": 4, "type": "CheckpointLoaderSimple", "mode": 0,
+                        "outputs": [{"type": "MODEL"}, {"type": "CLIP"}, {"type": "VAE"}],
+                        "widgets_values": ["model.safetensors"]
+                    },
+                    {
+                        "id": 5, "type": "LoraLoaderModelOnly", "mode": 4,
+                        "inputs": [{"name": "model", "type": "MODEL", "link": 1}],
+                        "outputs": [{"type": "MODEL"}],
+                        "widgets_values": ["lora.safetensors", 1]
+                    },
+                    {
+                        "id": 6, "type": "CLIPTextEncode", "mode": 0, "title": "Positive",
+                        "inputs": [
+                            {"name": "clip", "type": "CLIP", "link": 3},
+                            {"name": "text", "type": "STRING", "link": 5, "widget": {"name": "text"}}
+                        ],
+                        "widgets_values": ["a cat"]
+                    },
+                    {
+                        "id": 7, "type": "Reroute", "mode": 0,
+                        "inputs": [{"name": "", "type": "*", "link": 2}]
+                    },
+                    {"id": 8, "type": "PrimitiveNode", "mode": 0, "widgets_values": ["a cat"]},
+                    {"id": 9, "type": "Note", "mode": 0, "widgets_values": ["a note"]},
+                    {
+                        "id": 3, "type": "KSampler", "mode": 0,
+                        "inputs": [
+                            {"name": "model", "type": "MODEL", "link": 4},
+                            {"name": "positive", "type": "CONDITIONING", "link": 6}
+                        ],
+                        "widgets_values": [42, "randomize", 20]
+                    },
+                    {"id": 10, "type": "KSampler", "mode": 2, "widgets_values": [1, "fixed", 20]}
+                ],
+                "links": [
+                    [1, 4, 0, 5, 0, "MODEL"],
+                    [2, 4, 1, 7, 0, "CLIP"],
+                    [3, 7, 0, 6, 0, "CLIP"],
+                    [4, 5, 0, 3, 0, "MODEL"],
+                    [5, 8, 0, 6, 1, "STRING"],
+                    {"id": 6, "origin_id": 6, "origin_slot": 0, "target_id": 3, "target_slot": 3}
+                ]
+            }"#,
+            &object_info,
+        )
+        .unwrap();
+
+        assert_eq!(
+            workflow.into_value(),
+            json!({
+                "3": {
+                    "inputs": {"model": ["4", 0], "seed": 42, "steps": 20, "positive": ["6", 0]},
+                    "class_type": "KSampler",
+                    "_meta": {"title": "KSampler"}
+                },
+                "4": {
+                    "inputs": {"ckpt_name": "model.safetensors"},
+                    "class_type": "CheckpointLoaderSimple",
+                    "_meta": {"title": "Load Checkpoint"}
+                },
+                "6": {
+                    "inputs": {"text": "a cat", "clip": ["4", 1]},
+                    "class_type": "CLIPTextEncode",
+                    "_meta": {"title": "Positive"}
+                }
+            })
+        );
+
+        let unknown = json!({"nodes": [{"id": 1, "type": "Custom", "mode": 0}]});
+        assert!(matches!(
+            Workflow::from_ui_value(&unknown, &object_info),
+            Err(ClientError::InvalidUiWorkflow(_))
+        ));
+    }
+
+    /// A generator returning consecutive numbers.
+    struct Counter(u64);
+
+    impl RngCore for Counter {
+        fn next_u32(&mut self) -> u32 {
+            self.next_u64() as u32
+        }
+
+        fn next_u64(&mut self) -> u64 {
+            self.0 += 1;
+            self.0
+        }
+
+        fn fill_bytes(&mut self, dst: &mut [u8]) {
+            rand_core::impls::fill_bytes_via_next(self, dst)
+        }
+    }
+
+    #[test]
+    fn test_seeds() {
+        let mut workflow = Workflow::from_value(json!({
+            "3": {"class_type": "KSampler", "inputs": {"seed": 1}},
+            "5": {"class_type": "SamplerCustom", "inputs": {"noise_seed": 2}},
+            "7": {"class_type": "KSampler", "inputs": {"seed": ["8", 0]}},
+            "8": {"class_type": "Seed (rgthree)", "inputs": {"seed": 3}},
+            "12": {"class_type": "CLIPTextEncode", "inputs": {"text": "a cat"}}
+        }))
+        .unwrap();
+
+        workflow.set_seed("3", 42).unwrap();
+        assert_eq!(workflow.node_by_id("3").unwrap().seed(), Some(42));
+        assert!(matches!(
+            workflow.set_seed("7", 42),
+            Err(ClientError::NoSeedInput(node)) if node == "7"
+        ));
+        assert!(matches!(
+            workflow.set_seed("12", 42),
+            Err(ClientError::NoSeedInput(node)) if node == "12"
+        ));
+
+        let seeds = workflow.randomize_seeds(&mut Counter(0));
+        let seeds = seeds
+            .iter()
+            .map(|(id, seed)| (id.as_str(), *seed))
+            .collect::<Vec<_>>();
+        assert_eq!(seeds, [("3", 1), ("5", 2), ("8", 3)]);
+        assert_eq!(workflow.as_value()["5"]["inputs"]["noise_seed"], 2);
+        assert_eq!(workflow.as_value()["7"]["inputs"]["seed"], json!(["8", 0]));
+
+        let seeds = workflow.randomize_seeds(&mut Counter(u64::MAX - 3));
+        let seeds = seeds
+            .iter()
+            .map(|(id, seed)| (id.as_str(), *seed))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            seeds,
+            [
+                ("3", u64::MAX - 2),
+                ("5", u64::MAX - 1),
+                ("8", u64::MAX % FALLBACK_SEED_LIMIT)
+            ]
+        );
+    }
+}
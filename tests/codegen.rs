@@ -0,0 +1,72 @@
+use comfyui_client::{
+    codegen::generate_nodes,
+    meta::ObjectInfo,
+    workflow::{TypedNode, Workflow},
+};
+use serde_json::json;
+
+mod nodes {
+    include!("data/nodes.rs");
+}
+
+use nodes::{CLIPTextEncode, CheckpointLoaderSimple, KSampler, SaveImage};
+
+const CLASS_TYPES: &[&str] = &[
+    "CheckpointLoaderSimple",
+    "CLIPTextEncode",
+    "KSampler",
+    "SaveImage",
+];
+
+#[test]
+fn test_generated_nodes_up_to_date() {
+    let object_info =
+        serde_json::from_str::<ObjectInfo>(include_str!("data/object_info.json")).unwrap();
+    let code = generate_nodes(&object_info, CLASS_TYPES).unwrap();
+    if std::env::var_os("UPDATE_GENERATED").is_some() {
+        std::fs::write("tests/data/nodes.rs", &code).unwrap();
+    }
+    assert_eq!(code, include_str!("data/nodes.rs"));
+    assert!(generate_nodes(&object_info, &["Not Exists"]).is_err());
+}
+
+#[test]
+fn test_generated_nodes() {
+    let mut workflow = Workflow::new();
+    let checkpoint = workflow.add(&CheckpointLoaderSimple {
+        ckpt_name: "model.safetensors".to_string(),
+    });
+    let positive = workflow.add(&CLIPTextEncode {
+        text: "a cat".to_string(),
+        clip: checkpoint.output(1),
+    });
+    assert_eq!(KSampler::CLASS_TYPE, "KSampler");
+    let sampler = workflow.add(&KSampler {
+        model: checkpoint.output(0),
+        seed: 42,
+        steps: 20,
+        cfg: 8.0,
+        sampler_name: "euler".to_string(),
+        scheduler: "normal".to_string(),
+        positive: positive.output(0),
+        negative: positive.output(0),
+        latent_image: positive.output(0),
+        denoise: 1.0,
+    });
+    let save = workflow.add(&SaveImage {
+        images: sampler.output(0),
+        filename_prefix: "ComfyUI".to_string(),
+        r#type: None,
+    });
+    assert_eq!(
+        workflow.node_by_id(sampler.as_str()).unwrap().input("seed"),
+        Some(&json!(42))
+    );
+    assert_eq!(
+        workflow.into_value()[save.as_str()],
+        json!({
+            "class_type": "SaveImage",
+            "inputs": {"images": ["3", 0], "filename_prefix": "ComfyUI"}
+        })
+    );
+}
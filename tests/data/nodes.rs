@@ -0,0 +1,126 @@
+// @generated by comfyui_client::codegen::generate_nodes
+
+/// Load Checkpoint
+///
+/// Loads a diffusion model checkpoint, diffusion models are used to denoise latents.
+///
+/// Outputs:
+/// - 0: `MODEL` (MODEL)
+/// - 1: `CLIP` (CLIP)
+/// - 2: `VAE` (VAE)
+#[derive(Clone, Debug)]
+pub struct CheckpointLoaderSimple {
+    /// The `ckpt_name` input.
+    pub ckpt_name: String,
+}
+
+impl ::comfyui_client::workflow::TypedNode for CheckpointLoaderSimple {
+    const CLASS_TYPE: &'static str = "CheckpointLoaderSimple";
+
+    fn inputs(&self) -> ::serde_json::Value {
+        let mut inputs = ::serde_json::Map::new();
+        inputs.insert("ckpt_name".to_string(), ::serde_json::Value::from(self.ckpt_name.clone()));
+        ::serde_json::Value::Object(inputs)
+    }
+}
+
+/// CLIP Text Encode (Prompt)
+///
+/// Encodes a text prompt using a CLIP model into an embedding that can be used to guide the diffusion model towards generating specific images.
+///
+/// Outputs:
+/// - 0: `CONDITIONING` (CONDITIONING)
+#[derive(Clone, Debug)]
+pub struct CLIPTextEncode {
+    /// The `text` input.
+    pub text: String,
+    /// The `clip` input.
+    pub clip: ::comfyui_client::workflow::Link,
+}
+
+impl ::comfyui_client::workflow::TypedNode for CLIPTextEncode {
+    const CLASS_TYPE: &'static str = "CLIPTextEncode";
+
+    fn inputs(&self) -> ::serde_json::Value {
+        let mut inputs = ::serde_json::Map::new();
+        inputs.insert("text".to_string(), ::serde_json::Value::from(self.text.clone()));
+        inputs.insert("clip".to_string(), ::serde_json::Value::from(self.clip.clone()));
+        ::serde_json::Value::Object(inputs)
+    }
+}
+
+/// KSampler
+///
+/// Uses the provided model, positive and negative conditioning to denoise the latent image.
+///
+/// Outputs:
+/// - 0: `LATENT` (LATENT)
+#[derive(Clone, Debug)]
+pub struct KSampler {
+    /// The `model` input.
+    pub model: ::comfyui_client::workflow::Link,
+    /// The `seed` input.
+    pub seed: u64,
+    /// The `steps` input.
+    pub steps: u64,
+    /// The `cfg` input.
+    pub cfg: f64,
+    /// The `sampler_name` input.
+    pub sampler_name: String,
+    /// The `scheduler` input.
+    pub scheduler: String,
+    /// The `positive` input.
+    pub positive: ::comfyui_client::workflow::Link,
+    /// The `negative` input.
+    pub negative: ::comfyui_client::workflow::Link,
+    /// The `latent_image` input.
+    pub latent_image: ::comfyui_client::workflow::Link,
+    /// The `denoise` input.
+    pub denoise: f64,
+}
+
+impl ::comfyui_client::workflow::TypedNode for KSampler {
+    const CLASS_TYPE: &'static str = "KSampler";
+
+    fn inputs(&self) -> ::serde_json::Value {
+        let mut inputs = ::serde_json::Map::new();
+        inputs.insert("model".to_string(), ::serde_json::Value::from(self.model.clone()));
+        inputs.insert("seed".to_string(), ::serde_json::Value::from(self.seed));
+        inputs.insert("steps".to_string(), ::serde_json::Value::from(self.steps));
+        inputs.insert("cfg".to_string(), ::serde_json::Value::from(self.cfg));
+        inputs.insert("sampler_name".to_string(), ::serde_json::Value::from(self.sampler_name.clone()));
+        inputs.insert("scheduler".to_string(), ::serde_json::Value::from(self.scheduler.clone()));
+        inputs.insert("positive".to_string(), ::serde_json::Value::from(self.positive.clone()));
+        inputs.insert("negative".to_string(), ::serde_json::Value::from(self.negative.clone()));
+        inputs.insert("latent_image".to_string(), ::serde_json::Value::from(self.latent_image.clone()));
+        inputs.insert("denoise".to_string(), ::serde_json::Value::from(self.denoise));
+        ::serde_json::Value::Object(inputs)
+    }
+}
+
+/// Save Image
+///
+/// Saves the input images to your ComfyUI output directory.
+#[derive(Clone, Debug)]
+pub struct SaveImage {
+    /// The `images` input.
+    pub images: ::comfyui_client::workflow::Link,
+    /// The `filename_prefix` input.
+    pub filename_prefix: String,
+    /// The `type` input.
+    pub r#type: Option<String>,
+}
+
+impl ::comfyui_client::workflow::TypedNode for SaveImage {
+    const CLASS_TYPE: &'static str = "SaveImage";
+
+    fn inputs(&self) -> ::serde_json::Value {
+        let mut inputs = ::serde_json::Map::new();
+        inputs.insert("images".to_string(), ::serde_json::Value::from(self.images.clone()));
+        inputs.insert("filename_prefix".to_string(), ::serde_json::Value::from(self.filename_prefix.clone()));
+        if let Some(value) = &self.r#type {
+            inputs.insert("type".to_string(), ::serde_json::Value::from(value.clone()));
+        }
+        ::serde_json::Value::Object(inputs)
+    }
+}
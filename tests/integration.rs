@@ -1,7 +1,23 @@
 mod common;
 
 use bytes::Bytes;
-use comfyui_client::meta::{ComfyEvent, Event, FileInfo};
+use comfyui_client::{
+    meta::{
+        CancelOutcome, ComfyEvent, ConnectionEvent, ConnectionState, Event, FileInfo, PollOptions,
+        PromptOptions, UploadOptions, UploadProgress, ViewOptions,
+    },
+    workflow::Workflow,
+};
+use futures_util::TryStreamExt;
+use serde_json::json;
+use std::{
+    path::Path,
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::Duration,
+};
 use tokio::fs::{self, File};
 use tokio_stream::StreamExt;
 
@@ -10,6 +26,421 @@ async fn test_get_prompt() {
     common::setup();
     let (client, _) = common::build_client().await;
     client.get_prompt().await.unwrap();
+    client
+        .with_timeout(Duration::from_secs(5))
+        .get_prompt()
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_get_object_info() {
+    common::setup();
+    let (client, _) = common::build_client().await;
+    let object_info = client.get_object_info().await.unwrap();
+    assert!(object_info["SaveImage"].output_node);
+
+    let node_info = client.get_object_info_for("SaveImage").await.unwrap();
+    assert_eq!(node_info.unwrap().name, "SaveImage");
+    let node_info = client.get_object_info_for("Not Exists").await.unwrap();
+    assert!(node_info.is_none());
+}
+
+#[tokio::test]
+async fn test_get_embeddings() {
+    common::setup();
+    let (client, _) = common::build_client().await;
+    client.get_embeddings().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_get_extensions() {
+    common::setup();
+    let (client, _) = common::build_client().await;
+    client.get_extensions().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_get_logs() {
+    common::setup();
+    let (client, _) = common::build_client().await;
+    client.get_logs().await.unwrap();
+    let logs = client.get_raw_logs().await.unwrap();
+    assert!(!logs.entries.is_empty());
+}
+
+#[tokio::test]
+async fn test_subscribe_logs() {
+    common::setup();
+    let (client, _) = common::build_client().await;
+    client.subscribe_logs(true).await.unwrap();
+    client.subscribe_logs(false).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_reconnect_now() {
+    common::setup();
+    let (_, mut stream) = common::build_client().await;
+    let connection = stream.connection_handle();
+    assert_eq!(connection.state(), ConnectionState::Connected);
+
+    stream.reconnect_now();
+    while let Some(ev) = stream.next().await {
+        if let Event::Connection(ConnectionEvent::WSReconnectSuccess) = ev.unwrap() {
+            break;
+        }
+    }
+    assert_eq!(connection.state(), ConnectionState::Connected);
+}
+
+#[tokio::test]
+async fn test_send_ws_message() {
+    common::setup();
+    let (_, stream) = common::build_client().await;
+    let message = json!({
+        "type": "feature_flags",
+        "data": {"supports_preview_metadata": true},
+    });
+    stream
+        .connection_handle()
+        .send_ws_message(&message)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_close() {
+    common::setup();
+    let (_, stream) = common::build_client().await;
+    let connection = stream.connection_handle();
+    stream.close().await.unwrap();
+    assert_eq!(connection.state(), ConnectionState::Closed);
+}
+
+#[tokio::test]
+async fn test_get_folder_paths() {
+    common::setup();
+    let (client, _) = common::build_client().await;
+    let folder_paths = client.get_folder_paths().await.unwrap();
+    assert!(folder_paths.contains_key("checkpoints"));
+}
+
+#[tokio::test]
+async fn test_list_files() {
+    common::setup();
+    let (client, _) = common::build_client().await;
+    client.list_files("output").await.unwrap();
+    assert!(client.list_files("models").await.is_err());
+}
+
+#[tokio::test]
+async fn test_get_features() {
+    common::setup();
+    let (client, _) = common::build_client().await;
+    client.get_features().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_get_users() {
+    common::setup();
+    let (client, _) = common::build_client().await;
+    let users = client.get_users().await.unwrap();
+    assert_eq!(users.storage, "server");
+}
+
+#[tokio::test]
+async fn test_settings() {
+    common::setup();
+    let (client, _) = common::build_client().await;
+    let key = "ComfyUIClient.Test";
+    client.store_setting(key, &json!(42)).await.unwrap();
+    assert_eq!(client.get_setting(key).await.unwrap(), Some(json!(42)));
+    assert_eq!(client.get_settings().await.unwrap()[key], json!(42));
+    assert_eq!(
+        client.get_setting("ComfyUIClient.Missing").await.unwrap(),
+        None
+    );
+}
+
+#[tokio::test]
+async fn test_userdata() {
+    common::setup();
+    let (client, _) = common::build_client().await;
+    let file = "comfyui-client/test.json";
+    let moved = "comfyui-client/moved.json";
+
+    let path = client.store_userdata(file, "{}", true).await.unwrap();
+    assert_eq!(path, file);
+    assert_eq!(
+        client.get_userdata(file).await.unwrap(),
+        Some(Bytes::from("{}"))
+    );
+    assert!(
+        client
+            .list_userdata("comfyui-client", false)
+            .await
+            .unwrap()
+            .contains(&"test.json".to_string())
+    );
+
+    client.move_userdata(file, moved, true).await.unwrap();
+    assert_eq!(client.get_userdata(file).await.unwrap(), None);
+    client.delete_userdata(moved).await.unwrap();
+    assert_eq!(client.get_userdata(moved).await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn test_get_workflow_templates() {
+    common::setup();
+    let (client, _) = common::build_client().await;
+    client.get_workflow_templates().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_get_models() {
+    common::setup();
+    let (client, _) = common::build_client().await;
+    let folders = client.get_model_folders().await.unwrap();
+    assert!(folders.iter().any(|folder| folder == "checkpoints"));
+    client.get_models_in_folder("checkpoints").await.unwrap();
+}
+
+#[tokio::test]
+async fn test_get_system_stats() {
+    common::setup();
+    let (client, _) = common::build_client().await;
+    let stats = client.get_system_stats().await.unwrap();
+    assert!(!stats.devices.is_empty());
+}
+
+#[tokio::test]
+async fn test_get_histories() {
+    common::setup();
+    let (client, _) = common::build_client().await;
+    let histories = client.get_histories(Some(1)).await.unwrap();
+    assert!(histories.len() <= 1);
+    let streamed = client
+        .stream_histories(2)
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    assert!(streamed.len() >= histories.len());
+
+    client.delete_history(&["not-exists"]).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_post_prompt_with_options() {
+    common::setup();
+    let (client, _) = common::build_client().await;
+
+    let file = File::open("./tests/data/cat.webp").await.unwrap();
+    let file_info = FileInfo {
+        filename: "cat.webp".to_string(),
+        subfolder: "".to_string(),
+        r#type: "input".to_string(),
+    };
+    client.upload_image(file, &file_info, true).await.unwrap();
+
+    let workflow_json = fs::read_to_string("./tests/data/blur-cat-workflow.json")
+        .await
+        .unwrap();
+    let options = PromptOptions {
+        front: true,
+        ..Default::default()
+    };
+    let status = client
+        .post_prompt_with_options(&workflow_json, &options)
+        .await
+        .unwrap();
+    assert!(status.node_errors.is_empty());
+}
+
+#[tokio::test]
+async fn test_execute() {
+    common::setup();
+    let (client, mut stream) = common::build_client().await;
+
+    let file = File::open("./tests/data/cat.webp").await.unwrap();
+    let file_info = FileInfo {
+        filename: "cat.webp".to_string(),
+        subfolder: "".to_string(),
+        r#type: "input".to_string(),
+    };
+    client.upload_image(file, &file_info, true).await.unwrap();
+
+    let workflow_json = fs::read_to_string("./tests/data/blur-cat-workflow.json")
+        .await
+        .unwrap();
+    let outputs = client.execute(&mut stream, &workflow_json).await.unwrap();
+    assert_eq!(outputs.all_files().count(), 1);
+}
+
+#[tokio::test]
+async fn test_wait_for_completion_polling() {
+    common::setup();
+    let (client, _) = common::build_client().await;
+
+    let file = File::open("./tests/data/cat.webp").await.unwrap();
+    let file_info = FileInfo {
+        filename: "cat.webp".to_string(),
+        subfolder: "".to_string(),
+        r#type: "input".to_string(),
+    };
+    client.upload_image(file, &file_info, true).await.unwrap();
+
+    let workflow_json = fs::read_to_string("./tests/data/blur-cat-workflow.json")
+        .await
+        .unwrap();
+    let status = client.post_prompt(&workflow_json).await.unwrap();
+    let outputs = client
+        .wait_for_completion_polling(&status.prompt_id, &PollOptions::default())
+        .await
+        .unwrap();
+    assert_eq!(outputs.all_files().count(), 1);
+}
+
+#[tokio::test]
+async fn test_upload_image_from_path() {
+    common::setup();
+    let (client, _) = common::build_client().await;
+    let options = UploadOptions {
+        subfolder: "from-path".to_string(),
+        overwrite: true,
+        ..Default::default()
+    };
+    let info = client
+        .upload_image_from_path("./tests/data/cat.webp", &options)
+        .await
+        .unwrap();
+    assert_eq!(info.filename, "cat.webp");
+    assert_eq!(info.subfolder, "from-path");
+    assert_eq!(info.r#type, "input");
+}
+
+#[tokio::test]
+async fn test_upload_image_with_progress() {
+    common::setup();
+    let (client, _) = common::build_client().await;
+    let options = UploadOptions {
+        overwrite: true,
+        ..Default::default()
+    };
+    let len = std::fs::metadata("./tests/data/cat.webp").unwrap().len();
+    let sent = Arc::new(AtomicU64::new(0));
+    let sent_clone = sent.clone();
+    let info = client
+        .upload_image_from_path_with_progress(
+            "./tests/data/cat.webp",
+            &options,
+            move |progress: UploadProgress| {
+                assert_eq!(progress.total, Some(len));
+                sent_clone.store(progress.sent, Ordering::SeqCst);
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!(info.filename, "cat.webp");
+    assert_eq!(sent.load(Ordering::SeqCst), len);
+}
+
+#[tokio::test]
+async fn test_get_view_stream() {
+    common::setup();
+    let (client, _) = common::build_client().await;
+    let file = File::open("./tests/data/cat.webp").await.unwrap();
+    let file_info = FileInfo {
+        filename: "cat.webp".to_string(),
+        subfolder: "".to_string(),
+        r#type: "input".to_string(),
+    };
+    client.upload_image(file, &file_info, true).await.unwrap();
+    let len = std::fs::metadata("./tests/data/cat.webp").unwrap().len();
+
+    let stream = client.get_view_stream(&file_info).await.unwrap();
+    assert_eq!(stream.content_length(), Some(len));
+    let chunks = stream.try_collect::<Vec<_>>().await.unwrap();
+    assert_eq!(
+        chunks.iter().map(|chunk| chunk.len() as u64).sum::<u64>(),
+        len
+    );
+
+    let path = std::env::temp_dir().join("comfyui-client-view-stream.webp");
+    assert_eq!(
+        client.download_view_to(&file_info, &path).await.unwrap(),
+        len
+    );
+    fs::remove_file(&path).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_attach_input_image() {
+    common::setup();
+    let (client, mut stream) = common::build_client().await;
+
+    let workflow_json = fs::read_to_string("./tests/data/blur-cat-workflow.json")
+        .await
+        .unwrap();
+    let mut workflow = Workflow::from_json(&workflow_json).unwrap();
+    let info = workflow
+        .attach_input_image("1", &client, "./tests/data/cat.webp")
+        .await
+        .unwrap();
+    assert_eq!(
+        workflow.node_by_id("1").unwrap().input("image"),
+        Some(&json!(info.filename))
+    );
+    let outputs = client.execute(&mut stream, &workflow).await.unwrap();
+    assert_eq!(outputs.all_files().count(), 1);
+}
+
+#[tokio::test]
+async fn test_get_queue() {
+    common::setup();
+    let (client, _) = common::build_client().await;
+    let queue = client.get_queue().await.unwrap();
+    assert!(!queue.is_running("not-exists"));
+    assert!(!client.interrupt_prompt("not-exists").await.unwrap());
+}
+
+#[tokio::test]
+async fn test_delete_queue_items() {
+    common::setup();
+    let (client, _) = common::build_client().await;
+    client.delete_queue_items(&["not-exists"]).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_cancel_prompt() {
+    common::setup();
+    let (client, _) = common::build_client().await;
+    assert_eq!(
+        client.cancel_prompt("not-exists").await.unwrap(),
+        CancelOutcome::NotFound
+    );
+}
+
+#[tokio::test]
+async fn test_queue_position() {
+    common::setup();
+    let (client, mut stream) = common::build_client().await;
+    assert_eq!(client.queue_position("not-exists").await.unwrap(), None);
+    let positions = client
+        .queue_positions(&mut stream, "not-exists")
+        .collect::<Vec<_>>()
+        .await;
+    assert!(positions.is_empty());
+}
+
+#[tokio::test]
+async fn test_wait_for_queue_empty() {
+    common::setup();
+    let (client, mut stream) = common::build_client().await;
+    client
+        .wait_for_queue_empty(Some(&mut stream), Some(Duration::from_secs(60)))
+        .await
+        .unwrap();
+    client.wait_for_queue_empty(None, None).await.unwrap();
 }
 
 #[tokio::test]
@@ -50,22 +481,24 @@ async fn test_integration() {
         }
     }
 
-    let mut history = client
+    let history = client
         .get_history(&prompt.prompt_id)
         .await
         .unwrap()
         .unwrap();
-    let image = history
-        .outputs
-        .remove("5")
-        .unwrap()
-        .images
-        .unwrap()
-        .remove(0);
+    let image = history.output("5").unwrap().first_image().unwrap();
 
-    let image2_buf = client.get_view(&image).await.unwrap();
+    let image2_buf = client.get_view(image).await.unwrap();
 
     assert_eq!(image_buf, image2_buf);
+
+    let options = ViewOptions {
+        preview_format: Some("webp".to_string()),
+        quality: Some(50),
+        ..Default::default()
+    };
+    let preview_buf = client.get_view_with_options(image, &options).await.unwrap();
+    assert!(preview_buf.starts_with(b"RIFF"));
 }
 
 #[tokio::test]
@@ -110,3 +543,24 @@ async fn test_integration_with_stream_ext() {
     assert_eq!(files.len(), 1);
     assert_eq!(files[0][0].r#type, "temp");
 }
+
+#[tokio::test]
+async fn test_upload_dir() {
+    common::setup();
+    let (client, _) = common::build_client().await;
+
+    let uploaded = client
+        .upload_dir(
+            "./tests/data",
+            "upload-dir",
+            |path| path.extension().is_some_and(|ext| ext == "webp"),
+            true,
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(uploaded.len(), 2);
+    let info = &uploaded[Path::new("./tests/data/cat.webp")];
+    assert_eq!(info.filename, "cat.webp");
+    assert_eq!(info.subfolder, "upload-dir");
+}